@@ -11,13 +11,13 @@ pub enum KooPaaError {
     #[msg("Contribution amount must be greater than zero")]
     InvalidContributionAmount,
 
-    #[msg("Interval must be between 1 and 90 days")]
+    #[msg("Interval is outside the range allowed by the protocol")]
     InvalidInterval,
 
-    #[msg("Number of participants must be between 3 and 20")]
+    #[msg("Number of participants is outside the range allowed by the protocol")]
     InvalidParticipantCount,
 
-    #[msg("Group name is too long (maximum 50 characters)")]
+    #[msg("Group name is longer than the protocol allows")]
     NameTooLong,
 
     #[msg("Group has already started")]
@@ -65,7 +65,7 @@ pub enum KooPaaError {
     #[msg("Insufficient funds in token account")]
     InsufficientFunds,
 
-    #[msg("Fee percentage must be between 0 and 100")]
+    #[msg("Fee percentage must be between 0 and 100 (per-mille)")]
     InvalidFeePercentage,
 
     #[msg("You have already contributed to this round")]
@@ -82,4 +82,175 @@ pub enum KooPaaError {
 
     #[msg("No refunds is available for you to claim on this group")]
     NoRefundToClaim,
+
+    #[msg("Waiting room is full")]
+    WaitingRoomFull,
+
+    #[msg("Group admin cannot vacate their seat")]
+    AdminCannotVacate,
+
+    #[msg("Participant is not behind on contributions")]
+    ParticipantNotInArrears,
+
+    #[msg("Seat is not vacant")]
+    SeatNotVacant,
+
+    #[msg("Replacement is not on the group's standby list")]
+    NotOnStandby,
+
+    #[msg("The recipient seat for this round is vacant")]
+    RecipientSeatVacant,
+
+    #[msg("Treasury token account is required to collect the protocol fee")]
+    MissingTreasuryAccount,
+
+    #[msg("Admin commission exceeds the protocol maximum")]
+    InvalidAdminCommission,
+
+    #[msg("No admin commission is available to claim on this group")]
+    NoCommissionToClaim,
+
+    #[msg("Only the protocol authority can perform this action")]
+    OnlyProtocolAuthority,
+
+    #[msg("Protocol configuration is invalid")]
+    InvalidProtocolConfig,
+
+    #[msg("Signer is not the pending protocol authority")]
+    NotPendingAuthority,
+
+    #[msg("Protocol is paused")]
+    ProtocolPaused,
+
+    #[msg("Group is paused")]
+    GroupPaused,
+
+    #[msg("Group is already in the requested pause state")]
+    PauseStateUnchanged,
+
+    #[msg("You have already voted to change the pause state of this group")]
+    AlreadyVotedToPause,
+
+    #[msg("Account is already on the current layout version")]
+    AlreadyMigrated,
+
+    #[msg("Account data does not match any known layout")]
+    UnknownAccountLayout,
+
+    #[msg("Only the program upgrade authority can perform this action")]
+    OnlyUpgradeAuthority,
+
+    #[msg("There is no pending join request for this member")]
+    NoJoinRequest,
+
+    #[msg("Participant account does not match the expected member PDA")]
+    InvalidParticipantAccount,
+
+    #[msg("Token mint uses an extension the program does not support")]
+    UnsupportedMintExtension,
+
+    #[msg("Arithmetic overflow")]
+    MathOverflow,
+
+    #[msg("Token mint is not on the protocol allowlist")]
+    MintNotAllowed,
+
+    #[msg("Token mint is already on the protocol allowlist")]
+    MintAlreadyAllowed,
+
+    #[msg("Protocol mint allowlist is full")]
+    AllowedMintsFull,
+
+    #[msg("Use a native SOL group for SOL contributions")]
+    UseNativeSolGroup,
+
+    #[msg("Instruction is only available for native SOL groups")]
+    NotNativeSolGroup,
+
+    #[msg("Yield adapter is not approved by the protocol")]
+    YieldAdapterNotAllowed,
+
+    #[msg("Yield adapter is already approved")]
+    YieldAdapterAlreadyAllowed,
+
+    #[msg("Protocol yield adapter list is full")]
+    YieldAdaptersFull,
+
+    #[msg("Yield adapter does not match the one the group opted into")]
+    InvalidYieldAdapter,
+
+    #[msg("Yield adapter accounts are missing")]
+    MissingYieldAdapterAccounts,
+
+    #[msg("Group still has funds deposited with its yield adapter")]
+    YieldFundsDeposited,
+
+    #[msg("Cannot prepay beyond the group's last contribution round")]
+    PrepayBeyondLastRound,
+
+    #[msg("Installment must be positive and no more than what the round still needs")]
+    InvalidInstallmentAmount,
+
+    #[msg("Member has not delegated enough to the group for auto-debit")]
+    AutoDebitNotApproved,
+
+    #[msg("Keeper tip exceeds the protocol maximum")]
+    InvalidKeeperTip,
+
+    #[msg("This member has already received their payout")]
+    PayoutAlreadyReceived,
+
+    #[msg("Payout shares must be non-zero and add up to at most 10000 bps")]
+    InvalidPayoutShares,
+
+    #[msg("Payout share accounts are missing or out of order")]
+    InvalidPayoutShareAccount,
+
+    #[msg("Skip must cover at least one payout interval")]
+    InvalidSkipRounds,
+
+    #[msg("Maximum number of schedule skips reached")]
+    ScheduleSkipsFull,
+
+    #[msg("Proposal reason is too long")]
+    ReasonTooLong,
+
+    #[msg("Voting on this proposal has ended")]
+    ProposalVotingEnded,
+
+    #[msg("Proposal has not been approved")]
+    ProposalNotApproved,
+
+    #[msg("Proposal has already been executed")]
+    ProposalAlreadyExecuted,
+
+    #[msg("Member is already next in line or has been paid out")]
+    NotEligibleForHardshipPayout,
+
+    #[msg("Voting period, quorum or threshold is outside the allowed range")]
+    InvalidProposalTerms,
+
+    #[msg("You have not voted to close this group")]
+    NoCloseVote,
+
+    #[msg("Seat has been paid out; settle the remaining contributions before leaving")]
+    OutstandingContributionsAfterPayout,
+
+    #[msg("Seat has already been paid out and cannot be handed over")]
+    SeatAlreadyPaidOut,
+
+    #[msg("Member took their seat after this proposal was created")]
+    NotInElectorate,
+
+    #[msg("Proposal does not authorize this action")]
+    ProposalMismatch,
+
+    #[msg("Seat-fill proposals are carried out by filling the seat")]
+    ProposalExecutedByFill,
+
+    #[msg("Yield authority does not belong to this group")]
+    InvalidYieldAuthority,
+
+    #[msg("Yield adapters are not available for mints with transfer fees")]
+    YieldNotSupportedForMint,
 }
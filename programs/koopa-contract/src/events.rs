@@ -1,12 +1,29 @@
+use crate::state::{PayoutShare, ProposalKind, ProtocolConfig, TimeUnit, VoteChoice};
 use anchor_lang::prelude::*;
 
 #[event]
 pub struct AjoGroupCreatedEvent {
     pub group_name: String,
     pub contribution_amount: u64,
+    pub mint: Pubkey,
     pub num_participants: u8,
     pub contribution_interval: u8,
     pub payout_interval: u8,
+    pub time_unit: TimeUnit,
+    pub admin: Pubkey,
+    pub admin_commission: u8,
+}
+
+#[event]
+pub struct AjoGroupUpdatedEvent {
+    pub group_name: String,
+    pub contribution_amount: u64,
+    pub contribution_interval: u8,
+    pub payout_interval: u8,
+    pub time_unit: TimeUnit,
+    pub num_participants: u8,
+    pub joined_members: u8,
+    pub pending_requests: u8,
 }
 
 #[event]
@@ -23,6 +40,12 @@ pub struct JoinRequestRejectedEvent {
     pub participant: Pubkey,
 }
 
+#[event]
+pub struct JoinRequestWithdrawnEvent {
+    pub group_name: String,
+    pub participant: Pubkey,
+}
+
 #[event]
 pub struct ParticipantInWaitingRoomEvent {
     pub group_name: String,
@@ -38,17 +61,30 @@ pub struct AjoGroupStartedEvent {
 #[event]
 pub struct ContributionMadeEvent {
     pub group_name: String,
-    pub contributor: Pubkey,
+    pub contributor: Pubkey, // Member credited with the contribution
+    pub payer: Pubkey,       // Differs from `contributor` for sponsored contributions
     pub contribution_amount: u64,
+    pub fee_amount: u64,
     pub current_round: u16,
+    pub paid_until_round: u16, // Beyond `current_round` when rounds were prepaid
+    pub partial_amount: u64,   // Installments paid toward the round after it
 }
 
 #[event]
 pub struct PayoutMadeEvent {
     pub group_name: String,
     pub recipient: Pubkey,
-    pub payout_amount: u64,
+    pub payout_amount: u64, // Including the parts forwarded to `shares`
+    pub fee_amount: u64,
+    pub commission_amount: u64,
     pub payout_round: u16,
+    pub shares: Vec<PayoutShareTransfer>,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct PayoutShareTransfer {
+    pub destination: Pubkey,
+    pub amount: u64,
 }
 
 #[event]
@@ -63,4 +99,197 @@ pub struct RefundClaimedEvent {
     pub group_name: String,
     pub participant: Pubkey,
     pub amount: u64,
+    pub yield_amount: u64, // Share of the yield earned on idle funds
+}
+
+#[event]
+pub struct SeatVacatedEvent {
+    pub group_name: String,
+    pub participant: Pubkey,
+    pub seat_index: u8,
+    pub defaulted: bool,
+}
+
+#[event]
+pub struct SeatFilledEvent {
+    pub group_name: String,
+    pub previous_participant: Pubkey,
+    pub replacement: Pubkey,
+    pub seat_index: u8,
+    pub arrears_paid: u64,
+    pub fee_amount: u64,
+}
+
+#[event]
+pub struct AdminCommissionClaimedEvent {
+    pub group_name: String,
+    pub admin: Pubkey,
+    pub amount: u64,
+}
+
+#[event]
+pub struct ProtocolConfigUpdatedEvent {
+    pub authority: Pubkey,
+    pub config: ProtocolConfig,
+}
+
+#[event]
+pub struct AuthorityTransferStartedEvent {
+    pub authority: Pubkey,
+    pub pending_authority: Pubkey,
+}
+
+#[event]
+pub struct AuthorityTransferredEvent {
+    pub previous_authority: Pubkey,
+    pub new_authority: Pubkey,
+}
+
+#[event]
+pub struct AllowedMintUpdatedEvent {
+    pub authority: Pubkey,
+    pub mint: Pubkey,
+    pub allowed: bool,
+}
+
+#[event]
+pub struct ProtocolPauseUpdatedEvent {
+    pub authority: Pubkey,
+    pub paused: bool,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct GroupPauseVoteEvent {
+    pub group_name: String,
+    pub participant: Pubkey,
+    pub pause: bool,
+    pub total_votes: u8,
+}
+
+#[event]
+pub struct GroupPauseUpdatedEvent {
+    pub group_name: String,
+    pub paused: bool,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct ScheduleSkippedEvent {
+    pub group_name: String,
+    pub from_round: u16, // Contribution intervals elapsed when the skip starts
+    pub rounds: u16,     // Contribution intervals skipped
+}
+
+#[event]
+pub struct ProposalCreatedEvent {
+    pub group_name: String,
+    pub proposal: Pubkey,
+    pub proposer: Pubkey,
+    pub kind: ProposalKind,
+    pub reason: String,
+    pub deadline: i64,
+}
+
+#[event]
+pub struct ProposalVoteEvent {
+    pub proposal: Pubkey,
+    pub voter: Pubkey,
+    pub choice: VoteChoice,
+    pub yes_votes: u8,
+    pub no_votes: u8,
+    pub abstain_votes: u8,
+}
+
+#[event]
+pub struct ProposalExecutedEvent {
+    pub group_name: String,
+    pub proposal: Pubkey,
+    pub kind: ProposalKind,
+}
+
+#[event]
+pub struct PayoutOrderSwappedEvent {
+    pub group_name: String,
+    pub moved_up: Pubkey,   // Takes the next payout slot
+    pub moved_down: Pubkey, // Takes `moved_up`'s former slot
+    pub next_seat: u8,
+    pub former_seat: u8,
+}
+
+#[event]
+pub struct CloseVoteRevokedEvent {
+    pub group_name: String,
+    pub participant: Pubkey,
+    pub total_votes: u8,
+}
+
+#[event]
+pub struct CloseVotesExpiredEvent {
+    pub group_name: String,
+    pub expired_votes: u8,
+    pub total_votes: u8, // Live votes left
+}
+
+#[event]
+pub struct AccountMigratedEvent {
+    pub account: Pubkey,
+    pub from_version: u8,
+    pub to_version: u8,
+}
+
+#[event]
+pub struct YieldAdapterUpdatedEvent {
+    pub authority: Pubkey,
+    pub adapter: Pubkey,
+    pub allowed: bool,
+}
+
+#[event]
+pub struct GroupYieldAdapterSetEvent {
+    pub group_name: String,
+    pub adapter: Option<Pubkey>,
+}
+
+#[event]
+pub struct YieldDepositedEvent {
+    pub group_name: String,
+    pub adapter: Pubkey,
+    pub amount: u64,
+}
+
+#[event]
+pub struct YieldWithdrawnEvent {
+    pub group_name: String,
+    pub amount: u64,       // Returned to the vault
+    pub yield_amount: u64, // Part of `amount` that is yield
+}
+
+#[event]
+pub struct AutoDebitUpdatedEvent {
+    pub group_name: String,
+    pub participant: Pubkey,
+    pub token_account: Pubkey,
+    pub cap: u64, // 0 when the delegation was revoked
+}
+
+#[event]
+pub struct KeeperTipPaidEvent {
+    pub group_name: String,
+    pub keeper: Pubkey,
+    pub amount: u64,
+}
+
+#[event]
+pub struct PayoutDestinationSetEvent {
+    pub group_name: String,
+    pub participant: Pubkey,
+    pub destination: Option<Pubkey>, // None sends the payout to the member's own wallet
+}
+
+#[event]
+pub struct PayoutSharesSetEvent {
+    pub group_name: String,
+    pub participant: Pubkey,
+    pub shares: Vec<PayoutShare>,
 }
@@ -1,16 +1,20 @@
 //lib.rs
 use anchor_lang::prelude::*;
-use anchor_spl::token::{transfer, Mint, Token, TokenAccount, Transfer};
+use anchor_spl::token_interface::{
+    approve_checked, revoke, ApproveChecked, Mint, Revoke, TokenAccount, TokenInterface,
+};
 
 pub mod errors;
 pub mod events;
 pub mod state;
 pub mod utils;
+pub mod yield_adapter;
 
 use errors::*;
 use events::*;
 use state::*;
 use utils::*;
+use yield_adapter::*;
 
 // This is your program's public key and it will update
 // automatically when you build the project.
@@ -21,95 +25,425 @@ declare_id!("2ritrNbhimgFDysvHUgh1Cc5Xr5Z9qWmiumSH76WVzXh");
 mod koopa {
     use super::*;
 
-    pub fn initialize(ctx: Context<Initialize>) -> Result<()> {
+    pub fn initialize(ctx: Context<Initialize>, config: ProtocolConfig) -> Result<()> {
+        validate_protocol_config(&config)?;
+
         let global_state = &mut ctx.accounts.global_state;
 
+        global_state.version = GlobalState::CURRENT_VERSION;
+        global_state.authority = ctx.accounts.admin.key();
+        global_state.pending_authority = None;
         global_state.total_groups = 0;
         global_state.active_groups = 0;
+        global_state.config = config;
+        global_state.allowed_mints = vec![];
+        global_state.yield_adapters = vec![];
+        global_state.paused = false;
+        global_state.paused_at = 0;
+        global_state.total_paused_secs = 0;
 
         global_state.bumps = ctx.bumps.global_state;
 
         Ok(())
     }
 
-    pub fn create_ajo_group(
-        ctx: Context<CreateAjoGroup>,
-        name: String,
-        contribution_amount: u64,
-        contribution_interval: u8,
-        payout_interval: u8,
-        num_participants: u8,
+    pub fn update_protocol_config(
+        ctx: Context<UpdateProtocolConfig>,
+        config: ProtocolConfig,
+    ) -> Result<()> {
+        validate_protocol_config(&config)?;
+
+        let global_state = &mut ctx.accounts.global_state;
+        global_state.config = config;
+
+        emit!(ProtocolConfigUpdatedEvent {
+            authority: global_state.authority,
+            config,
+        });
+
+        Ok(())
+    }
+
+    pub fn transfer_authority(
+        ctx: Context<UpdateProtocolConfig>,
+        new_authority: Pubkey,
     ) -> Result<()> {
+        let global_state = &mut ctx.accounts.global_state;
+        global_state.pending_authority = Some(new_authority);
+
+        emit!(AuthorityTransferStartedEvent {
+            authority: global_state.authority,
+            pending_authority: new_authority,
+        });
+
+        Ok(())
+    }
+
+    pub fn accept_authority(ctx: Context<AcceptAuthority>) -> Result<()> {
+        let global_state = &mut ctx.accounts.global_state;
+        let new_authority = ctx.accounts.new_authority.key();
+
         require!(
-            contribution_amount > 0,
-            KooPaaError::InvalidContributionAmount
+            global_state.pending_authority == Some(new_authority),
+            KooPaaError::NotPendingAuthority
         );
+
+        let previous_authority = global_state.authority;
+        global_state.authority = new_authority;
+        global_state.pending_authority = None;
+
+        emit!(AuthorityTransferredEvent {
+            previous_authority,
+            new_authority,
+        });
+
+        Ok(())
+    }
+
+    pub fn add_allowed_mint(ctx: Context<UpdateProtocolConfig>, mint: Pubkey) -> Result<()> {
+        let global_state = &mut ctx.accounts.global_state;
+
         require!(
-            contribution_interval > 0 && contribution_interval <= 90,
-            KooPaaError::InvalidInterval
+            !global_state.allowed_mints.contains(&mint),
+            KooPaaError::MintAlreadyAllowed
         );
         require!(
-            payout_interval >= 7 && payout_interval <= 90,
-            KooPaaError::InvalidInterval
+            global_state.allowed_mints.len() < GlobalState::MAX_ALLOWED_MINTS,
+            KooPaaError::AllowedMintsFull
         );
+
+        global_state.allowed_mints.push(mint);
+
+        emit!(AllowedMintUpdatedEvent {
+            authority: global_state.authority,
+            mint,
+            allowed: true,
+        });
+
+        Ok(())
+    }
+
+    // Existing groups keep working with a removed mint; only new groups are blocked
+    pub fn remove_allowed_mint(ctx: Context<UpdateProtocolConfig>, mint: Pubkey) -> Result<()> {
+        let global_state = &mut ctx.accounts.global_state;
+
+        let index = global_state
+            .allowed_mints
+            .iter()
+            .position(|m| *m == mint)
+            .ok_or(KooPaaError::MintNotAllowed)?;
+        global_state.allowed_mints.remove(index);
+
+        emit!(AllowedMintUpdatedEvent {
+            authority: global_state.authority,
+            mint,
+            allowed: false,
+        });
+
+        Ok(())
+    }
+
+    pub fn add_yield_adapter(ctx: Context<UpdateProtocolConfig>, adapter: Pubkey) -> Result<()> {
+        let global_state = &mut ctx.accounts.global_state;
+
         require!(
-            payout_interval >= contribution_interval,
-            KooPaaError::InvalidInterval
+            !global_state.yield_adapters.contains(&adapter),
+            KooPaaError::YieldAdapterAlreadyAllowed
         );
         require!(
-            num_participants >= 3 && num_participants <= 20,
-            KooPaaError::InvalidParticipantCount
+            global_state.yield_adapters.len() < GlobalState::MAX_YIELD_ADAPTERS,
+            KooPaaError::YieldAdaptersFull
         );
-        require!(name.len() <= 50, KooPaaError::NameTooLong);
 
-        let group = &mut ctx.accounts.ajo_group;
-        let creator = &ctx.accounts.creator;
+        global_state.yield_adapters.push(adapter);
+
+        emit!(YieldAdapterUpdatedEvent {
+            authority: global_state.authority,
+            adapter,
+            allowed: true,
+        });
+
+        Ok(())
+    }
+
+    // Groups using a removed adapter can no longer deposit but can still withdraw
+    pub fn remove_yield_adapter(ctx: Context<UpdateProtocolConfig>, adapter: Pubkey) -> Result<()> {
+        let global_state = &mut ctx.accounts.global_state;
+
+        let index = global_state
+            .yield_adapters
+            .iter()
+            .position(|a| *a == adapter)
+            .ok_or(KooPaaError::YieldAdapterNotAllowed)?;
+        global_state.yield_adapters.remove(index);
+
+        emit!(YieldAdapterUpdatedEvent {
+            authority: global_state.authority,
+            adapter,
+            allowed: false,
+        });
+
+        Ok(())
+    }
+
+    pub fn set_protocol_paused(ctx: Context<UpdateProtocolConfig>, paused: bool) -> Result<()> {
         let global_state = &mut ctx.accounts.global_state;
         let clock = Clock::get()?;
 
-        let interval = payout_interval as f64 / contribution_interval as f64;
-        let round_payout_interval = interval.ceil() as u8 * contribution_interval;
-
-        group.name = name.clone();
-        group.contribution_amount = contribution_amount;
-        group.contribution_interval = contribution_interval;
-        group.payout_interval = round_payout_interval;
-        group.num_participants = num_participants;
-
-        group.participants = vec![AjoParticipant {
-            pubkey: creator.key(),
-            contribution_round: 0,
-            refund_amount: 0,
-        }];
-        group.payout_round = 0;
-        group.start_timestamp = None;
-        group.close_votes = vec![];
-        group.waiting_room = vec![];
-        group.is_closed = false;
-
-        let (_group_pda, group_bump) =
-            Pubkey::find_program_address(&[b"ajo-group", group.name.as_bytes()], ctx.program_id);
+        require!(
+            global_state.paused != paused,
+            KooPaaError::PauseStateUnchanged
+        );
+
+        if paused {
+            global_state.paused_at = clock.unix_timestamp;
+        } else {
+            global_state.total_paused_secs += clock.unix_timestamp - global_state.paused_at;
+        }
+        global_state.paused = paused;
+
+        emit!(ProtocolPauseUpdatedEvent {
+            authority: global_state.authority,
+            paused,
+            timestamp: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    pub fn migrate_global_state(
+        ctx: Context<MigrateGlobalState>,
+        config: ProtocolConfig,
+    ) -> Result<()> {
+        validate_protocol_config(&config)?;
+
+        let account = ctx.accounts.global_state.to_account_info();
+        let authority = ctx.accounts.authority.key();
+
+        let migrated = {
+            let data = account.try_borrow_data()?;
+            require!(
+                data.len() >= 8 && data[..8] == *GlobalState::DISCRIMINATOR,
+                KooPaaError::UnknownAccountLayout
+            );
+
+            if let Ok(current) = GlobalState::try_deserialize(&mut &data[..]) {
+                require!(
+                    current.version != GlobalState::CURRENT_VERSION,
+                    KooPaaError::AlreadyMigrated
+                );
+            }
+
+            GlobalStateV0::deserialize(&mut &data[8..])
+                .map_err(|_| KooPaaError::UnknownAccountLayout)?
+                .into_current(authority, config)
+        };
+
+        resize_account(
+            &account,
+            &ctx.accounts.authority.to_account_info(),
+            &ctx.accounts.system_program.to_account_info(),
+            GlobalState::SIZE,
+        )?;
+        migrated.try_serialize(&mut &mut account.try_borrow_mut_data()?[..])?;
+
+        emit!(AccountMigratedEvent {
+            account: account.key(),
+            from_version: 0,
+            to_version: GlobalState::CURRENT_VERSION,
+        });
+
+        Ok(())
+    }
+
+    pub fn migrate_ajo_group<'info>(
+        ctx: Context<'_, '_, 'info, 'info, MigrateAjoGroup<'info>>,
+    ) -> Result<()> {
+        let account = ctx.accounts.ajo_group.to_account_info();
+        let payer = ctx.accounts.payer.to_account_info();
+        let system_program = ctx.accounts.system_program.to_account_info();
+        let group_key = account.key();
+        let clock = Clock::get()?;
+
+        let (migrated, members, from_version) = {
+            let data = account.try_borrow_data()?;
+            require!(
+                data.len() >= 8 && data[..8] == *AjoGroup::DISCRIMINATOR,
+                KooPaaError::UnknownAccountLayout
+            );
+
+            if let Ok(current) = AjoGroup::try_deserialize(&mut &data[..]) {
+                require!(
+                    current.version != AjoGroup::CURRENT_VERSION,
+                    KooPaaError::AlreadyMigrated
+                );
+            }
+
+            // Protocol pauses before the migration never applied to the old layout
+            let protocol_pause_offset = ctx.accounts.global_state.paused_secs(clock.unix_timestamp);
+            let (legacy, from_version) = AjoGroupV1::decode(&data[8..], protocol_pause_offset)?;
+
+            let mint = ctx.accounts.group_token_vault.mint;
+            let close_vote_expires_at = close_vote_expiry(
+                ctx.accounts.global_state.config.close_vote_expiry_secs,
+                clock.unix_timestamp,
+            );
+            let (migrated, members) = legacy.into_current(group_key, mint, close_vote_expires_at);
+            (migrated, members, from_version)
+        };
+
+        let expected_group = Pubkey::create_program_address(
+            &[b"ajo-group", migrated.name.as_bytes(), &[migrated.bumps]],
+            ctx.program_id,
+        )
+        .map_err(|_| KooPaaError::UnknownAccountLayout)?;
+        require_keys_eq!(expected_group, group_key, KooPaaError::UnknownAccountLayout);
+
+        // Member records are created from the remaining accounts, which must
+        // list the participant PDAs of the seated members and then the waiting
+        // room, in the order the old account stored them
+        require!(
+            ctx.remaining_accounts.len() == members.len(),
+            KooPaaError::InvalidParticipantAccount
+        );
+
+        for (member, participant_info) in members.into_iter().zip(ctx.remaining_accounts) {
+            let (expected_participant, bump) = Pubkey::find_program_address(
+                &[b"participant", group_key.as_ref(), member.pubkey.as_ref()],
+                ctx.program_id,
+            );
+            require_keys_eq!(
+                expected_participant,
+                participant_info.key(),
+                KooPaaError::InvalidParticipantAccount
+            );
+
+            create_pda_account(
+                participant_info,
+                &payer,
+                &system_program,
+                AjoParticipant::SIZE,
+                &[
+                    b"participant",
+                    group_key.as_ref(),
+                    member.pubkey.as_ref(),
+                    &[bump],
+                ],
+            )?;
+
+            let member = AjoParticipant {
+                bumps: bump,
+                ..member
+            };
+            member.try_serialize(&mut &mut participant_info.try_borrow_mut_data()?[..])?;
+        }
+
+        resize_account(
+            &account,
+            &payer,
+            &system_program,
+            AjoGroup::calculate_size(&migrated.name, migrated.num_participants),
+        )?;
+        migrated.try_serialize(&mut &mut account.try_borrow_mut_data()?[..])?;
+
+        emit!(AccountMigratedEvent {
+            account: group_key,
+            from_version,
+            to_version: AjoGroup::CURRENT_VERSION,
+        });
+
+        Ok(())
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    pub fn create_ajo_group(
+        ctx: Context<CreateAjoGroup>,
+        name: String,
+        contribution_amount: u64,
+        contribution_interval: u8,
+        payout_interval: u8,
+        num_participants: u8,
+        admin_commission: u8,
+        time_unit: TimeUnit,
+    ) -> Result<()> {
+        let mint = ctx.accounts.token_mint.key();
+        require!(
+            ctx.accounts.global_state.allowed_mints.contains(&mint),
+            KooPaaError::MintNotAllowed
+        );
+        // Wrapped SOL is served by native SOL groups instead
+        require!(
+            mint != AjoGroup::NATIVE_MINT,
+            KooPaaError::UseNativeSolGroup
+        );
+        validate_mint_extensions(&ctx.accounts.token_mint.to_account_info())?;
+
+        init_group(
+            &mut ctx.accounts.ajo_group,
+            &mut ctx.accounts.creator_participant,
+            ctx.bumps.creator_participant,
+            &mut ctx.accounts.global_state,
+            ctx.accounts.creator.key(),
+            name,
+            GroupTerms {
+                contribution_amount,
+                contribution_interval,
+                payout_interval,
+                time_unit,
+            },
+            num_participants,
+            admin_commission,
+            mint,
+            Clock::get()?.unix_timestamp,
+        )?;
+
+        let group = &mut ctx.accounts.ajo_group;
         let (_vault_pda, vault_bump) =
             Pubkey::find_program_address(&[b"group-vault", group.key().as_ref()], ctx.program_id);
-
-        group.bumps = group_bump;
+        group.bumps = ctx.bumps.ajo_group;
         group.vault_bump = vault_bump;
 
-        global_state.total_groups += 1;
+        Ok(())
+    }
+
+    pub fn update_ajo_group(
+        ctx: Context<UpdateAjoGroup>,
+        contribution_amount: u64,
+        contribution_interval: u8,
+        payout_interval: u8,
+        num_participants: u8,
+        time_unit: TimeUnit,
+    ) -> Result<()> {
+        let group = &mut ctx.accounts.ajo_group;
+        let admin = &ctx.accounts.admin;
+
+        require!(!group.is_closed, KooPaaError::GroupAlreadyClosed);
+        require!(group.admin == admin.key(), KooPaaError::OnlyAdminCanUpdate);
 
-        emit!(AjoGroupCreatedEvent {
-            group_name: name.clone(),
+        let terms = GroupTerms {
             contribution_amount,
-            num_participants,
             contribution_interval,
-            payout_interval: round_payout_interval,
-        });
+            payout_interval,
+            time_unit,
+        };
+        update_group_terms(
+            group,
+            &ctx.accounts.global_state.config.limits,
+            terms,
+            num_participants,
+        )?;
 
-        emit!(ParticipantJoinedEvent {
-            group_name: name.clone(),
-            participant: creator.key(),
-            join_timestamp: clock.unix_timestamp,
-            admin_invited: false,
+        // Members who joined or asked to join under the previous terms are
+        // notified through this event so clients can prompt them to review it
+        emit!(AjoGroupUpdatedEvent {
+            group_name: group.name.clone(),
+            contribution_amount,
+            contribution_interval,
+            payout_interval: group.payout_interval,
+            time_unit,
+            num_participants,
+            joined_members: group.seats_filled - 1,
+            pending_requests: group.pending_requests,
         });
 
         Ok(())
@@ -118,24 +452,36 @@ mod koopa {
     pub fn request_join_ajo_group(ctx: Context<RequestJoinAjoGroup>) -> Result<()> {
         let group = &mut ctx.accounts.ajo_group;
         let participant = &ctx.accounts.participant;
+        let ajo_participant = &mut ctx.accounts.ajo_participant;
+
+        // Once the group has started the waiting room acts as a standby list
+        // for replacing members whose seats have been vacated
+        require!(!group.is_closed, KooPaaError::GroupAlreadyClosed);
+
+        // An existing record means this wallet has been part of the group before;
+        // only members whose seat has been handed over may ask to come back
+        if ajo_participant.group == group.key() {
+            match ajo_participant.status {
+                ParticipantStatus::Pending => return err!(KooPaaError::AlreadyRequested),
+                ParticipantStatus::Active | ParticipantStatus::Vacant => {
+                    return err!(KooPaaError::AlreadyJoined)
+                }
+                ParticipantStatus::Left => {}
+            }
+        }
 
         require!(
-            group.start_timestamp.is_none(),
-            KooPaaError::GroupAlreadyStarted
+            group.pending_requests < group.num_participants,
+            KooPaaError::WaitingRoomFull
         );
 
-        let already_joined = group
-            .participants
-            .iter()
-            .any(|p| p.pubkey == participant.key());
-
-        require!(!already_joined, KooPaaError::AlreadyJoined);
-
-        let already_requested = group.waiting_room.contains(&participant.key());
-        require!(!already_requested, KooPaaError::AlreadyRequested);
+        ajo_participant.set_inner(AjoParticipant::new(
+            group.key(),
+            participant.key(),
+            ctx.bumps.ajo_participant,
+        ));
+        group.pending_requests += 1;
 
-        group.waiting_room.push(participant.key());
-        
         let group_name = group.name.clone();
         emit!(ParticipantInWaitingRoomEvent {
             group_name,
@@ -145,57 +491,76 @@ mod koopa {
         Ok(())
     }
 
-    pub fn approve_join_request(ctx: Context<ApproveJoinRequest>,
-        approve: bool,) -> Result<()> {
+    // Take back a join request, from the waiting room or the standby list
+    pub fn withdraw_join_request(ctx: Context<WithdrawJoinRequest>) -> Result<()> {
+        let group = &mut ctx.accounts.ajo_group;
+        group.pending_requests -= 1;
+
+        emit!(JoinRequestWithdrawnEvent {
+            group_name: group.name.clone(),
+            participant: ctx.accounts.participant.key(),
+        });
+
+        Ok(())
+    }
+
+    pub fn approve_join_request(ctx: Context<ApproveJoinRequest>, approve: bool) -> Result<()> {
         let group = &mut ctx.accounts.ajo_group;
         let global_state = &mut ctx.accounts.global_state;
         let participant = &ctx.accounts.participant;
-        let possible_admin = &ctx.accounts.caller;
+        let ajo_participant = &mut ctx.accounts.ajo_participant;
         let clock = Clock::get()?;
-        let mut admin_invited = false;
         let group_name = group.name.clone();
-        
+
         require!(
-            group.start_timestamp.is_none(),
-            KooPaaError::GroupAlreadyStarted
+            group.admin == ctx.accounts.caller.key(),
+            KooPaaError::OnlyAdminCanUpdate
         );
+
+        // A record that did not exist before this instruction means the member
+        // never asked to join → mark as admin invited
+        let has_record = ajo_participant.group == group.key();
         require!(
-            !group.participants.is_empty(),
-            KooPaaError::GroupHasNoAdmin
+            !has_record || ajo_participant.status == ParticipantStatus::Pending,
+            KooPaaError::AlreadyJoined
         );
+        let admin_invited = !has_record;
 
-        let admin = group.participants[0].pubkey;
-        require!(admin == possible_admin.key(), KooPaaError::OnlyAdminCanUpdate);
-        
-        let already_joined = group
-            .participants
-            .iter()
-            .any(|p| p.pubkey == participant.key());
-        require!(!already_joined, KooPaaError::AlreadyJoined);
-
-        if let Some(pos) = group.waiting_room.iter().position(|p| *p == participant.key()) {
-            group.waiting_room.remove(pos);
-        } else {
-            // not in waiting room → mark as admin invited
-            admin_invited = true;
+        if has_record {
+            group.pending_requests -= 1;
         }
 
         if approve {
-            group.participants.push(AjoParticipant {
-                pubkey: participant.key(),
-                contribution_round: 0,
-                refund_amount: 0,
+            // After the start, members come in through `fill_vacant_seat`;
+            // standby requests can still be rejected to clear the list
+            require!(!global_state.paused, KooPaaError::ProtocolPaused);
+            require!(group.paused_at.is_none(), KooPaaError::GroupPaused);
+            require!(
+                group.start_timestamp.is_none(),
+                KooPaaError::GroupAlreadyStarted
+            );
+
+            ajo_participant.set_inner(AjoParticipant {
+                status: ParticipantStatus::Active,
+                seat: group.seats_filled,
+                first_proposal: group.proposal_count,
+                ..AjoParticipant::new(group.key(), participant.key(), ctx.bumps.ajo_participant)
             });
-            
-            if group.participants.len() == group.num_participants as usize {
+
+            group.seats_filled += 1;
+            group.active_members += 1;
+            group.add_seat_round(0);
+
+            if group.seats_filled == group.num_participants {
                 group.start_timestamp = Some(clock.unix_timestamp);
+                group.protocol_pause_offset = global_state.paused_secs(clock.unix_timestamp);
                 global_state.active_groups += 1;
                 emit!(AjoGroupStartedEvent {
                     group_name: group_name.clone(),
                     start_timestamp: clock.unix_timestamp
                 });
             }
-    
+
             emit!(ParticipantJoinedEvent {
                 group_name,
                 participant: participant.key(),
@@ -203,322 +568,2215 @@ mod koopa {
                 admin_invited,
             });
         } else {
+            require!(has_record, KooPaaError::NoJoinRequest);
+            ajo_participant.close(participant.to_account_info())?;
+
             emit!(JoinRequestRejectedEvent {
                 group_name,
                 participant: participant.key(),
-            });            
+            });
         }
-        
+
         Ok(())
     }
 
-    pub fn contribute(ctx: Context<Contribute>) -> Result<()> {
-        let group = &mut ctx.accounts.ajo_group;
-        let contributor = &ctx.accounts.contributor;
-        let clock = Clock::get()?;
+    pub fn contribute(ctx: Context<Contribute>, prepay_rounds: u16) -> Result<()> {
+        let transfer = ContributionTransfer {
+            from: ctx.accounts.contributor_token_account.to_account_info(),
+            authority: ctx.accounts.contributor.to_account_info(),
+            vault: ctx.accounts.group_token_vault.to_account_info(),
+            treasury: ctx
+                .accounts
+                .treasury_token_account
+                .as_ref()
+                .map(|treasury| treasury.to_account_info()),
+            program: TransferProgram::Token {
+                mint: ctx.accounts.token_mint.to_account_info(),
+                decimals: ctx.accounts.token_mint.decimals,
+                token_program: ctx.accounts.token_program.to_account_info(),
+            },
+        };
 
-        require!(
-            group.start_timestamp.is_some(),
-            KooPaaError::GroupNotStarted
-        );
+        settle_contribution(
+            &mut ctx.accounts.ajo_group,
+            &ctx.accounts.global_state,
+            &mut ctx.accounts.ajo_participant,
+            &transfer,
+            prepay_rounds,
+            Clock::get()?.unix_timestamp,
+        )
+    }
 
-        let start_timestamp = group.start_timestamp.unwrap();
-        let contribution_interval = group.contribution_interval;
-        let contribution_amount = group.contribution_amount;
+    // Pay toward the next unpaid round in installments. The round only counts
+    // as contributed once the installments reach the full contribution amount.
+    pub fn contribute_installment(ctx: Context<Contribute>, amount: u64) -> Result<()> {
+        let transfer = ContributionTransfer {
+            from: ctx.accounts.contributor_token_account.to_account_info(),
+            authority: ctx.accounts.contributor.to_account_info(),
+            vault: ctx.accounts.group_token_vault.to_account_info(),
+            treasury: ctx
+                .accounts
+                .treasury_token_account
+                .as_ref()
+                .map(|treasury| treasury.to_account_info()),
+            program: TransferProgram::Token {
+                mint: ctx.accounts.token_mint.to_account_info(),
+                decimals: ctx.accounts.token_mint.decimals,
+                token_program: ctx.accounts.token_program.to_account_info(),
+            },
+        };
 
-        let participant = group
-            .participants
-            .iter_mut()
-            .find(|p| p.pubkey == contributor.key())
-            .ok_or(KooPaaError::NotParticipant)?;
+        settle_installment(
+            &mut ctx.accounts.ajo_group,
+            &ctx.accounts.global_state,
+            &mut ctx.accounts.ajo_participant,
+            &transfer,
+            amount,
+            Clock::get()?.unix_timestamp,
+        )
+    }
 
-        let time_since_start = clock.unix_timestamp - start_timestamp;
-        let contribution_interval_seconds = contribution_interval as i64 * 86400;
-        let current_round = (time_since_start / contribution_interval_seconds) as u16;
+    // Pay a member's contribution from someone else's wallet. The payer signs and
+    // funds the transfer while the round is credited to the beneficiary.
+    pub fn contribute_for_member(
+        ctx: Context<ContributeForMember>,
+        prepay_rounds: u16,
+    ) -> Result<()> {
+        let transfer = ContributionTransfer {
+            from: ctx.accounts.payer_token_account.to_account_info(),
+            authority: ctx.accounts.payer.to_account_info(),
+            vault: ctx.accounts.group_token_vault.to_account_info(),
+            treasury: ctx
+                .accounts
+                .treasury_token_account
+                .as_ref()
+                .map(|treasury| treasury.to_account_info()),
+            program: TransferProgram::Token {
+                mint: ctx.accounts.token_mint.to_account_info(),
+                decimals: ctx.accounts.token_mint.decimals,
+                token_program: ctx.accounts.token_program.to_account_info(),
+            },
+        };
 
-        let last_paid_round = participant.contribution_round;
-        require!(
-            last_paid_round < current_round,
-            KooPaaError::AlreadyContributed
-        );
+        settle_contribution(
+            &mut ctx.accounts.ajo_group,
+            &ctx.accounts.global_state,
+            &mut ctx.accounts.ajo_participant,
+            &transfer,
+            prepay_rounds,
+            Clock::get()?.unix_timestamp,
+        )
+    }
 
-        let rounds_missed = current_round - last_paid_round;
-        let transfer_amount = contribution_amount * rounds_missed as u64;
+    // Delegate up to `cap` tokens to the group PDA so anyone can collect this
+    // member's contributions when they fall due. A token account has a single
+    // delegate, so this replaces any earlier approval on the same account.
+    pub fn approve_auto_debit(ctx: Context<UpdateAutoDebit>, cap: u64) -> Result<()> {
+        let group = &ctx.accounts.ajo_group;
+        require!(!group.is_closed, KooPaaError::GroupAlreadyClosed);
+        require!(cap > 0, KooPaaError::InvalidContributionAmount);
 
-        let transfer_accounts = Transfer {
-            from: ctx.accounts.contributor_token_account.to_account_info(),
-            to: ctx.accounts.group_token_vault.to_account_info(),
-            authority: contributor.to_account_info(),
+        let approve_accounts = ApproveChecked {
+            to: ctx.accounts.participant_token_account.to_account_info(),
+            mint: ctx.accounts.token_mint.to_account_info(),
+            delegate: group.to_account_info(),
+            authority: ctx.accounts.participant.to_account_info(),
         };
 
-        transfer(
+        approve_checked(
             CpiContext::new(
                 ctx.accounts.token_program.to_account_info(),
-                transfer_accounts,
+                approve_accounts,
             ),
-            transfer_amount,
+            cap,
+            ctx.accounts.token_mint.decimals,
         )?;
 
-        participant.contribution_round = current_round;
+        emit!(AutoDebitUpdatedEvent {
+            group_name: group.name.clone(),
+            participant: ctx.accounts.participant.key(),
+            token_account: ctx.accounts.participant_token_account.key(),
+            cap,
+        });
+
+        Ok(())
+    }
 
-        emit!(ContributionMadeEvent {
+    pub fn revoke_auto_debit(ctx: Context<UpdateAutoDebit>) -> Result<()> {
+        let group = &ctx.accounts.ajo_group;
+        let token_account = &ctx.accounts.participant_token_account;
+
+        // Leave delegations to anything other than this group alone
+        require!(
+            token_account.delegate == Some(group.key()).into(),
+            KooPaaError::AutoDebitNotApproved
+        );
+
+        let revoke_accounts = Revoke {
+            source: token_account.to_account_info(),
+            authority: ctx.accounts.participant.to_account_info(),
+        };
+
+        revoke(CpiContext::new(
+            ctx.accounts.token_program.to_account_info(),
+            revoke_accounts,
+        ))?;
+
+        emit!(AutoDebitUpdatedEvent {
             group_name: group.name.clone(),
-            contributor: contributor.key(),
-            contribution_amount: transfer_amount,
-            current_round,
+            participant: ctx.accounts.participant.key(),
+            token_account: token_account.key(),
+            cap: 0,
         });
 
         Ok(())
     }
 
-    pub fn payout(ctx: Context<Payout>) -> Result<()> {
-        let authority_info = ctx.accounts.ajo_group.to_account_info();
+    // Permissionless crank: pull the contribution due for the current round from
+    // a member who approved auto-debit, crediting it exactly like `contribute`
+    pub fn collect_contribution(ctx: Context<CollectContribution>) -> Result<()> {
         let group = &mut ctx.accounts.ajo_group;
+        let global_state = &ctx.accounts.global_state;
+        let participant = &mut ctx.accounts.ajo_participant;
+        let member_token_account = &ctx.accounts.participant_token_account;
         let clock = Clock::get()?;
 
+        let current_round = open_contribution_round(group, global_state, clock.unix_timestamp)?;
+        let (paid_until_round, transfer_amount) =
+            contribution_due(group, participant, current_round, 0)?;
+        let mint_info = ctx.accounts.token_mint.to_account_info();
+
+        let fee_amount = contribution_fee(&global_state.config, transfer_amount);
+        // The member pays the keeper tip on top for having been collected from
+        let caller = ctx.accounts.caller.key();
+        let keeper_tip = match ctx.accounts.keeper_token_account {
+            Some(_) if caller != participant.pubkey => {
+                calculate_fee(transfer_amount, global_state.config.keeper_tip)
+            }
+            _ => 0,
+        };
+        let gross_amount = amount_with_transfer_fee(&mint_info, transfer_amount)?;
+        let gross_fee = amount_with_transfer_fee(&mint_info, fee_amount)?;
+        let gross_tip = amount_with_transfer_fee(&mint_info, keeper_tip)?;
+
         require!(
-            ctx.accounts.recipient.mint == ctx.accounts.token_mint.key(),
-            KooPaaError::InvalidTokenAccountMint
+            member_token_account.delegate == Some(group.key()).into()
+                && member_token_account.delegated_amount >= gross_amount + gross_fee + gross_tip,
+            KooPaaError::AutoDebitNotApproved
         );
 
-        let start_timestamp = group.start_timestamp.ok_or(KooPaaError::GroupNotStarted)?;
-        let time_since_start = clock.unix_timestamp - start_timestamp;
+        let group_name = group.name.clone();
+        let signer_seeds: &[&[u8]] = &[b"ajo-group", group_name.as_bytes(), &[group.bumps]];
+        let member_transfer = ContributionTransfer {
+            from: member_token_account.to_account_info(),
+            authority: group.to_account_info(),
+            vault: ctx.accounts.group_token_vault.to_account_info(),
+            treasury: ctx
+                .accounts
+                .treasury_token_account
+                .as_ref()
+                .map(|treasury| treasury.to_account_info()),
+            program: TransferProgram::Token {
+                mint: mint_info,
+                decimals: ctx.accounts.token_mint.decimals,
+                token_program: ctx.accounts.token_program.to_account_info(),
+            },
+        };
 
-        let required_contributions_per_payout = group.payout_interval / group.contribution_interval;
-        let min_required_contribution_rounds =
-            (group.payout_round + 1) * required_contributions_per_payout as u16;
+        pay_contribution(
+            &member_transfer,
+            &global_state.config,
+            transfer_amount,
+            &[signer_seeds],
+        )?;
 
-        for p in &group.participants {
-            require!(
-                p.contribution_round >= min_required_contribution_rounds,
-                KooPaaError::NotAllContributed
-            );
+        if let Some(keeper_token_account) = ctx.accounts.keeper_token_account.as_ref() {
+            if keeper_tip > 0 {
+                transfer_from_payer(
+                    &member_transfer,
+                    &keeper_token_account.to_account_info(),
+                    keeper_tip,
+                    &[signer_seeds],
+                )?;
+
+                emit!(KeeperTipPaidEvent {
+                    group_name: group_name.clone(),
+                    keeper: caller,
+                    amount: keeper_tip,
+                });
+            }
         }
 
-        let payout_interval_secs = group.payout_interval as i64 * 86400;
-        let expected_payout_round = (time_since_start / payout_interval_secs) as u16;
+        record_contribution(group, participant, paid_until_round);
+
+        emit!(ContributionMadeEvent {
+            group_name,
+            contributor: participant.pubkey,
+            payer: participant.pubkey,
+            contribution_amount: transfer_amount,
+            fee_amount,
+            current_round,
+            paid_until_round,
+            partial_amount: 0,
+        });
+
+        Ok(())
+    }
+
+    pub fn payout<'info>(ctx: Context<'_, '_, 'info, 'info, Payout<'info>>) -> Result<()> {
+        let authority_info = ctx.accounts.ajo_group.to_account_info();
+        let group = &mut ctx.accounts.ajo_group;
+        let global_state = &ctx.accounts.global_state;
+        let clock = Clock::get()?;
 
+        require!(!global_state.paused, KooPaaError::ProtocolPaused);
+        require!(group.paused_at.is_none(), KooPaaError::GroupPaused);
         require!(
-            group.payout_round < expected_payout_round,
-            KooPaaError::PayoutNotYetDue
+            ctx.accounts.recipient.mint == ctx.accounts.token_mint.key(),
+            KooPaaError::InvalidTokenAccountMint
         );
 
-        let num_participants = group.participants.len() as u8;
-        let recipient_index = (group.payout_round as usize) % (num_participants as usize);
-        let recipient_pubkey = group.participants[recipient_index].pubkey;
+        let recipient_participant = &ctx.accounts.recipient_participant;
+        // Keepers opt into the tip by passing a token account; members paying
+        // out their own turn are not tipped
+        let caller = ctx.accounts.caller.key();
+        let tip_keeper =
+            caller != recipient_participant.pubkey && ctx.accounts.keeper_token_account.is_some();
+        let split = payout_split(
+            group,
+            global_state,
+            recipient_participant,
+            clock.unix_timestamp,
+            tip_keeper,
+        )?;
 
         require!(
-            recipient_pubkey == ctx.accounts.recipient.owner,
+            recipient_participant.payout_wallet() == ctx.accounts.recipient.owner,
             KooPaaError::NotCurrentRecipient
         );
 
-        let group_name = group.name.clone();
-        let signer_seeds = &[b"ajo-group", group_name.as_bytes(), &[group.bumps]];
-
-        let payout_amount = group.contribution_amount
-            * (num_participants as u64)
-            * (required_contributions_per_payout as u64);
-        let transfer_accounts = Transfer {
-            from: ctx.accounts.group_token_vault.to_account_info(),
-            to: ctx.accounts.recipient.to_account_info(),
-            authority: authority_info,
-        };
-
-        transfer(
-            CpiContext::new_with_signer(
-                ctx.accounts.token_program.to_account_info(),
-                transfer_accounts,
-                &[signer_seeds],
-            ),
-            payout_amount,
+        // `remaining_accounts` holds the payout share token accounts first, then
+        // the yield adapter accounts
+        let share_transfers = payout_share_transfers(
+            &recipient_participant.payout_shares,
+            ctx.remaining_accounts,
+            split.payout_amount,
+        )?;
+        let (share_accounts, adapter_accounts) =
+            ctx.remaining_accounts.split_at(share_transfers.len());
+
+        // Funds parked in the yield adapter are pulled back just in time; the
+        // vault must also keep the admin's commission claimable
+        let required = split.payout_amount
+            + split.fee_amount
+            + split.keeper_tip
+            + group.admin_commission_accrued
+            + split.commission_amount;
+        let vault_balance = ctx.accounts.group_token_vault.amount;
+        if required > vault_balance && group.yield_deposited > 0 {
+            let group_vault = GroupVault {
+                group: authority_info.clone(),
+                vault: ctx.accounts.group_token_vault.to_account_info(),
+                mint: ctx.accounts.token_mint.to_account_info(),
+                token_program: ctx.accounts.token_program.to_account_info(),
+            };
+            let (returned, yield_amount) = withdraw_from_yield(
+                group,
+                adapter_accounts,
+                &group_vault,
+                &mut ctx.accounts.group_token_vault,
+                required - vault_balance,
+            )?;
+
+            emit!(YieldWithdrawnEvent {
+                group_name: group.name.clone(),
+                amount: returned,
+                yield_amount,
+            });
+        }
+
+        let group_name = group.name.clone();
+        let funds = GroupFunds::Token {
+            group: authority_info,
+            vault: ctx.accounts.group_token_vault.to_account_info(),
+            mint: ctx.accounts.token_mint.to_account_info(),
+            decimals: ctx.accounts.token_mint.decimals,
+            token_program: ctx.accounts.token_program.to_account_info(),
+            signer_seeds: &[b"ajo-group", group_name.as_bytes(), &[group.bumps]],
+        };
+
+        distribute_payout(
+            group,
+            &funds,
+            &split,
+            recipient_participant.pubkey,
+            &ctx.accounts.recipient.to_account_info(),
+            share_transfers,
+            share_accounts,
+            ctx.accounts
+                .treasury_token_account
+                .as_ref()
+                .map(|treasury| treasury.to_account_info()),
+            ctx.accounts
+                .keeper_token_account
+                .as_ref()
+                .map(|keeper| keeper.to_account_info()),
+            caller,
+        )
+    }
+
+    // Vote to close the group. A member whose vote still counts may call this
+    // again to close the group once the votes already cast are a majority,
+    // e.g. after members have left.
+    pub fn close_ajo_group(ctx: Context<CloseAjoGroup>) -> Result<()> {
+        let group = &mut ctx.accounts.ajo_group;
+        let participant = &mut ctx.accounts.ajo_participant;
+        let global_state = &mut ctx.accounts.global_state;
+        let clock = Clock::get()?;
+
+        require!(!group.is_closed, KooPaaError::GroupAlreadyClosed);
+        let expired_votes = expire_close_votes(group, clock.unix_timestamp);
+        if expired_votes > 0 {
+            emit!(CloseVotesExpiredEvent {
+                group_name: group.name.clone(),
+                expired_votes,
+                total_votes: group.close_vote_expiries.len() as u8,
+            });
+        }
+
+        // A lapsed vote no longer reflects the member's will, so it is cast afresh
+        if !has_live_close_vote(participant, clock.unix_timestamp) {
+            withdraw_close_vote(group, participant);
+            let expires_at = close_vote_expiry(
+                global_state.config.close_vote_expiry_secs,
+                clock.unix_timestamp,
+            );
+            cast_close_vote(group, participant, expires_at);
+        } else {
+            require!(close_vote_passed(group), KooPaaError::AlreadyVotedToClose);
+        }
+
+        if close_vote_passed(group) {
+            close_group(group, global_state);
+
+            emit!(AjoGroupClosedEvent {
+                group_name: group.name.clone(),
+                total_votes: group.close_vote_expiries.len() as u8,
+                group_size: group.active_members,
+            });
+        }
+
+        Ok(())
+    }
+
+    pub fn revoke_close_vote(ctx: Context<CloseAjoGroup>) -> Result<()> {
+        let group = &mut ctx.accounts.ajo_group;
+        let participant = &mut ctx.accounts.ajo_participant;
+        let clock = Clock::get()?;
+
+        require!(!group.is_closed, KooPaaError::GroupAlreadyClosed);
+        let expired_votes = expire_close_votes(group, clock.unix_timestamp);
+        if expired_votes > 0 {
+            emit!(CloseVotesExpiredEvent {
+                group_name: group.name.clone(),
+                expired_votes,
+                total_votes: group.close_vote_expiries.len() as u8,
+            });
+        }
+
+        require!(
+            has_live_close_vote(participant, clock.unix_timestamp),
+            KooPaaError::NoCloseVote
+        );
+        withdraw_close_vote(group, participant);
+
+        emit!(CloseVoteRevokedEvent {
+            group_name: group.name.clone(),
+            participant: participant.pubkey,
+            total_votes: group.close_vote_expiries.len() as u8,
+        });
+
+        Ok(())
+    }
+
+    pub fn vote_group_pause(ctx: Context<VoteGroupPause>, pause: bool) -> Result<()> {
+        let group = &mut ctx.accounts.ajo_group;
+        let global_state = &ctx.accounts.global_state;
+        let participant = &mut ctx.accounts.ajo_participant;
+        let clock = Clock::get()?;
+
+        require!(!global_state.paused, KooPaaError::ProtocolPaused);
+        require!(!group.is_closed, KooPaaError::GroupAlreadyClosed);
+        // Pausing only makes sense once the schedule is running
+        require!(
+            group.start_timestamp.is_some(),
+            KooPaaError::GroupNotStarted
+        );
+        require!(
+            group.paused_at.is_some() != pause,
+            KooPaaError::PauseStateUnchanged
+        );
+
+        let already_voted = participant.pause_vote_epoch == group.pause_vote_epoch;
+        require!(!already_voted, KooPaaError::AlreadyVotedToPause);
+
+        participant.pause_vote_epoch = group.pause_vote_epoch;
+        group.pause_vote_count += 1;
+        let total_votes = group.pause_vote_count as usize;
+
+        emit!(GroupPauseVoteEvent {
+            group_name: group.name.clone(),
+            participant: participant.pubkey,
+            pause,
+            total_votes: total_votes as u8,
+        });
+
+        if total_votes * 2 > group.active_members as usize {
+            let protocol_paused = global_state.paused_secs(clock.unix_timestamp);
+
+            if pause {
+                group.paused_at = Some(clock.unix_timestamp);
+                group.pause_protocol_snapshot = protocol_paused;
+            } else if let Some(paused_at) = group.paused_at {
+                group.paused_secs += (clock.unix_timestamp - paused_at)
+                    - (protocol_paused - group.pause_protocol_snapshot);
+                group.paused_at = None;
+            }
+            group.pause_vote_epoch += 1;
+            group.pause_vote_count = 0;
+
+            emit!(GroupPauseUpdatedEvent {
+                group_name: group.name.clone(),
+                paused: pause,
+                timestamp: clock.unix_timestamp,
+            });
+        }
+
+        Ok(())
+    }
+
+    // Put a group decision to a vote. `voting_period` is in seconds; quorum and
+    // threshold are percentages no lower than the protocol minimums.
+    pub fn create_proposal(
+        ctx: Context<CreateProposal>,
+        kind: ProposalKind,
+        reason: String,
+        voting_period: i64,
+        quorum: u8,
+        threshold: u8,
+    ) -> Result<()> {
+        let group = &mut ctx.accounts.ajo_group;
+        let participant = &ctx.accounts.proposer_participant;
+        let clock = Clock::get()?;
+
+        require!(!group.is_closed, KooPaaError::GroupAlreadyClosed);
+        require!(
+            reason.len() <= Proposal::MAX_REASON_LENGTH,
+            KooPaaError::ReasonTooLong
+        );
+        require!(
+            (Proposal::MIN_VOTING_PERIOD_SECS..=Proposal::MAX_VOTING_PERIOD_SECS)
+                .contains(&voting_period)
+                && (Proposal::MIN_QUORUM..=100).contains(&quorum)
+                && (Proposal::MIN_THRESHOLD..100).contains(&threshold),
+            KooPaaError::InvalidProposalTerms
+        );
+
+        match kind {
+            ProposalKind::HardshipPayout => {
+                require!(
+                    group.start_timestamp.is_some(),
+                    KooPaaError::GroupNotStarted
+                );
+                require!(
+                    participant.seat as u16 > group.payout_round,
+                    KooPaaError::NotEligibleForHardshipPayout
+                );
+            }
+            ProposalKind::ScheduleSkip { payout_rounds } => {
+                require!(
+                    group.start_timestamp.is_some(),
+                    KooPaaError::GroupNotStarted
+                );
+                validate_skip_rounds(group, payout_rounds)?;
+            }
+            ProposalKind::RemoveMember { member } => {
+                require!(member != group.admin, KooPaaError::AdminCannotVacate);
+            }
+            ProposalKind::SetAdminCommission { admin_commission } => {
+                require!(
+                    admin_commission <= ctx.accounts.global_state.config.max_admin_commission,
+                    KooPaaError::InvalidAdminCommission
+                );
+            }
+            ProposalKind::SetYieldAdapter { adapter } => {
+                validate_yield_adapter(group, &ctx.accounts.global_state, adapter)?;
+            }
+            ProposalKind::UpdateTerms(terms) => {
+                // Checked against a copy; the terms only change once approved
+                update_group_terms(
+                    &mut (**group).clone(),
+                    &ctx.accounts.global_state.config.limits,
+                    terms,
+                    group.num_participants,
+                )?;
+            }
+            ProposalKind::FillSeat { seat, .. } => {
+                require!(
+                    group.start_timestamp.is_some(),
+                    KooPaaError::GroupNotStarted
+                );
+                require!(
+                    seat < group.num_participants,
+                    KooPaaError::InvalidProposalTerms
+                );
+            }
+            ProposalKind::ChangeAdmin { .. } => {}
+        }
+
+        let deadline = clock.unix_timestamp + voting_period;
+        let proposal_key = ctx.accounts.proposal.key();
+        ctx.accounts.proposal.set_inner(Proposal {
+            group: group.key(),
+            id: group.proposal_count,
+            proposer: participant.pubkey,
+            kind,
+            reason: reason.clone(),
+            deadline,
+            electorate: group.active_members,
+            quorum,
+            threshold,
+            yes_votes: 0,
+            no_votes: 0,
+            abstain_votes: 0,
+            executed: false,
+            bumps: ctx.bumps.proposal,
+        });
+        group.proposal_count += 1;
+
+        emit!(ProposalCreatedEvent {
+            group_name: group.name.clone(),
+            proposal: proposal_key,
+            proposer: participant.pubkey,
+            kind,
+            reason,
+            deadline,
+        });
+
+        Ok(())
+    }
+
+    pub fn vote_on_proposal(ctx: Context<VoteOnProposal>, choice: VoteChoice) -> Result<()> {
+        let proposal = &mut ctx.accounts.proposal;
+        let clock = Clock::get()?;
+
+        require!(
+            !ctx.accounts.ajo_group.is_closed,
+            KooPaaError::GroupAlreadyClosed
+        );
+        require!(!proposal.executed, KooPaaError::ProposalAlreadyExecuted);
+        require!(
+            clock.unix_timestamp <= proposal.deadline,
+            KooPaaError::ProposalVotingEnded
+        );
+        // Members seated after the proposal was created are not in its electorate
+        require!(
+            ctx.accounts.voter_participant.first_proposal <= proposal.id,
+            KooPaaError::NotInElectorate
+        );
+
+        match choice {
+            VoteChoice::Yes => proposal.yes_votes += 1,
+            VoteChoice::No => proposal.no_votes += 1,
+            VoteChoice::Abstain => proposal.abstain_votes += 1,
+        }
+
+        ctx.accounts.vote.set_inner(ProposalVote {
+            proposal: proposal.key(),
+            voter: ctx.accounts.voter.key(),
+            choice,
+            bumps: ctx.bumps.vote,
+        });
+
+        emit!(ProposalVoteEvent {
+            proposal: proposal.key(),
+            voter: ctx.accounts.voter.key(),
+            choice,
+            yes_votes: proposal.yes_votes,
+            no_votes: proposal.no_votes,
+            abstain_votes: proposal.abstain_votes,
+        });
+
+        Ok(())
+    }
+
+    // Carry out an approved proposal. Anyone may call it; proposals that act on
+    // members take their participant accounts as `member_participant` and, for
+    // hardship payouts, `displaced_participant`.
+    pub fn execute_proposal(ctx: Context<ExecuteProposal>) -> Result<()> {
+        let group = &mut ctx.accounts.ajo_group;
+        let global_state = &ctx.accounts.global_state;
+        let proposal = &mut ctx.accounts.proposal;
+        let clock = Clock::get()?;
+
+        require!(!group.is_closed, KooPaaError::GroupAlreadyClosed);
+        require!(!proposal.executed, KooPaaError::ProposalAlreadyExecuted);
+        require!(
+            proposal.is_approved(clock.unix_timestamp),
+            KooPaaError::ProposalNotApproved
+        );
+
+        let group_key = group.key();
+        let group_name = group.name.clone();
+        let member_participant = ctx
+            .accounts
+            .member_participant
+            .as_deref_mut()
+            .filter(|p| p.group == group_key);
+
+        match proposal.kind {
+            ProposalKind::HardshipPayout => {
+                let requester = member_participant
+                    .filter(|p| p.pubkey == proposal.proposer)
+                    .ok_or(KooPaaError::InvalidParticipantAccount)?;
+                let displaced = ctx
+                    .accounts
+                    .displaced_participant
+                    .as_deref_mut()
+                    .filter(|p| p.group == group_key)
+                    .ok_or(KooPaaError::InvalidParticipantAccount)?;
+
+                let former_seat = swap_payout_seats(group, requester, displaced)?;
+
+                emit!(PayoutOrderSwappedEvent {
+                    group_name: group_name.clone(),
+                    moved_up: requester.pubkey,
+                    moved_down: displaced.pubkey,
+                    next_seat: requester.seat,
+                    former_seat,
+                });
+            }
+            ProposalKind::RemoveMember { member } => {
+                let participant = member_participant
+                    .filter(|p| p.pubkey == member && p.is_active())
+                    .ok_or(KooPaaError::InvalidParticipantAccount)?;
+                // The admin may have changed since the proposal was created
+                require!(member != group.admin, KooPaaError::AdminCannotVacate);
+                vacate_participant(group, participant);
+
+                emit!(SeatVacatedEvent {
+                    group_name: group_name.clone(),
+                    participant: member,
+                    seat_index: participant.seat,
+                    defaulted: true,
+                });
+            }
+            ProposalKind::ScheduleSkip { payout_rounds } => {
+                let time_since_start =
+                    active_time_since_start(group, global_state, clock.unix_timestamp)
+                        .ok_or(KooPaaError::GroupNotStarted)?;
+                let skip = schedule_skip(group, time_since_start, payout_rounds)?;
+
+                emit!(ScheduleSkippedEvent {
+                    group_name: group_name.clone(),
+                    from_round: skip.from_round,
+                    rounds: skip.rounds,
+                });
+            }
+            ProposalKind::SetAdminCommission { admin_commission } => {
+                require!(
+                    admin_commission <= global_state.config.max_admin_commission,
+                    KooPaaError::InvalidAdminCommission
+                );
+                group.admin_commission = admin_commission;
+            }
+            ProposalKind::ChangeAdmin { admin } => {
+                member_participant
+                    .filter(|p| p.pubkey == admin && p.is_active())
+                    .ok_or(KooPaaError::InvalidParticipantAccount)?;
+                group.admin = admin;
+            }
+            ProposalKind::SetYieldAdapter { adapter } => {
+                // The adapter may have been delisted, or funds deposited, since
+                validate_yield_adapter(group, global_state, adapter)?;
+                group.yield_adapter = adapter;
+
+                emit!(GroupYieldAdapterSetEvent {
+                    group_name: group_name.clone(),
+                    adapter,
+                });
+            }
+            ProposalKind::UpdateTerms(terms) => {
+                // Limits may have changed, or the group filled up, since
+                let num_participants = group.num_participants;
+                update_group_terms(group, &global_state.config.limits, terms, num_participants)?;
+
+                emit!(AjoGroupUpdatedEvent {
+                    group_name: group_name.clone(),
+                    contribution_amount: terms.contribution_amount,
+                    contribution_interval: terms.contribution_interval,
+                    payout_interval: group.payout_interval,
+                    time_unit: terms.time_unit,
+                    num_participants,
+                    joined_members: group.seats_filled - 1,
+                    pending_requests: group.pending_requests,
+                });
+            }
+            ProposalKind::FillSeat { .. } => return err!(KooPaaError::ProposalExecutedByFill),
+        }
+
+        proposal.executed = true;
+
+        emit!(ProposalExecutedEvent {
+            group_name,
+            proposal: proposal.key(),
+            kind: proposal.kind,
+        });
+
+        Ok(())
+    }
+
+    pub fn claim_refund<'info>(
+        ctx: Context<'_, '_, 'info, 'info, ClaimRefund<'info>>,
+    ) -> Result<()> {
+        let authority_info = ctx.accounts.ajo_group.to_account_info();
+        let group = &mut ctx.accounts.ajo_group;
+
+        check_refunds_open(group, &ctx.accounts.global_state)?;
+
+        // The first claim after closing brings the whole yield position back so
+        // every member's share of it is known
+        if group.yield_deposited > 0 {
+            let group_vault = GroupVault {
+                group: authority_info.clone(),
+                vault: ctx.accounts.group_token_vault.to_account_info(),
+                mint: ctx.accounts.token_mint.to_account_info(),
+                token_program: ctx.accounts.token_program.to_account_info(),
+            };
+            let (returned, yield_amount) = withdraw_from_yield(
+                group,
+                ctx.remaining_accounts,
+                &group_vault,
+                &mut ctx.accounts.group_token_vault,
+                WITHDRAW_ALL,
+            )?;
+
+            emit!(YieldWithdrawnEvent {
+                group_name: group.name.clone(),
+                amount: returned,
+                yield_amount,
+            });
+        }
+
+        let group_name = group.name.clone();
+        let funds = GroupFunds::Token {
+            group: authority_info,
+            vault: ctx.accounts.group_token_vault.to_account_info(),
+            mint: ctx.accounts.token_mint.to_account_info(),
+            decimals: ctx.accounts.token_mint.decimals,
+            token_program: ctx.accounts.token_program.to_account_info(),
+            signer_seeds: &[b"ajo-group", group_name.as_bytes(), &[group.bumps]],
+        };
+
+        pay_refund(
+            group,
+            &mut ctx.accounts.ajo_participant,
+            &funds,
+            &ctx.accounts.participant_token_account.to_account_info(),
+        )
+    }
+
+    pub fn claim_admin_commission(ctx: Context<ClaimAdminCommission>) -> Result<()> {
+        let group_name = ctx.accounts.ajo_group.name.clone();
+        let funds = GroupFunds::Token {
+            group: ctx.accounts.ajo_group.to_account_info(),
+            vault: ctx.accounts.group_token_vault.to_account_info(),
+            mint: ctx.accounts.token_mint.to_account_info(),
+            decimals: ctx.accounts.token_mint.decimals,
+            token_program: ctx.accounts.token_program.to_account_info(),
+            signer_seeds: &[
+                b"ajo-group",
+                group_name.as_bytes(),
+                &[ctx.accounts.ajo_group.bumps],
+            ],
+        };
+
+        pay_admin_commission(
+            &mut ctx.accounts.ajo_group,
+            ctx.accounts.admin.key(),
+            &funds,
+            &ctx.accounts.admin_token_account.to_account_info(),
+        )
+    }
+
+    pub fn deposit_idle_funds<'info>(
+        ctx: Context<'_, '_, 'info, 'info, DepositIdleFunds<'info>>,
+        amount: u64,
+    ) -> Result<()> {
+        let authority_info = ctx.accounts.ajo_group.to_account_info();
+        let group = &mut ctx.accounts.ajo_group;
+        let global_state = &ctx.accounts.global_state;
+
+        require!(!global_state.paused, KooPaaError::ProtocolPaused);
+        require!(group.paused_at.is_none(), KooPaaError::GroupPaused);
+        require!(!group.is_closed, KooPaaError::GroupAlreadyClosed);
+        require!(
+            group.admin == ctx.accounts.admin.key(),
+            KooPaaError::OnlyAdminCanUpdate
+        );
+        // Fees withheld on the way in and out would eat into the principal
+        require!(
+            !has_transfer_fee(&ctx.accounts.token_mint.to_account_info())?,
+            KooPaaError::YieldNotSupportedForMint
+        );
+
+        let adapter_key = group
+            .yield_adapter
+            .ok_or(KooPaaError::InvalidYieldAdapter)?;
+        require!(
+            global_state.yield_adapters.contains(&adapter_key),
+            KooPaaError::YieldAdapterNotAllowed
+        );
+
+        // Accrued commission stays in the vault so the admin can always claim it
+        let idle_balance = ctx
+            .accounts
+            .group_token_vault
+            .amount
+            .saturating_sub(group.admin_commission_accrued);
+        require!(
+            amount > 0 && amount <= idle_balance,
+            KooPaaError::InsufficientFunds
+        );
+
+        let group_name = group.name.clone();
+        let signer_seeds: &[&[u8]] = &[b"ajo-group", group_name.as_bytes(), &[group.bumps]];
+        let group_vault = GroupVault {
+            group: authority_info,
+            vault: ctx.accounts.group_token_vault.to_account_info(),
+            mint: ctx.accounts.token_mint.to_account_info(),
+            token_program: ctx.accounts.token_program.to_account_info(),
+        };
+
+        let adapter = YieldAdapter::from_remaining_accounts(
+            ctx.remaining_accounts,
+            adapter_key,
+            group_vault.group.key(),
+        )?;
+        adapter.deposit(&group_vault, amount, signer_seeds)?;
+
+        group.yield_deposited += amount;
+
+        emit!(YieldDepositedEvent {
+            group_name,
+            adapter: adapter_key,
+            amount,
+        });
+
+        Ok(())
+    }
+
+    // Send this member's payout to another wallet. Can be changed until their
+    // turn has been paid out.
+    pub fn set_payout_destination(
+        ctx: Context<SetPayoutDestination>,
+        destination: Option<Pubkey>,
+    ) -> Result<()> {
+        let group = &ctx.accounts.ajo_group;
+        let participant = &mut ctx.accounts.ajo_participant;
+
+        require!(!group.is_closed, KooPaaError::GroupAlreadyClosed);
+        require!(
+            group.payout_round <= participant.seat as u16,
+            KooPaaError::PayoutAlreadyReceived
+        );
+
+        participant.payout_destination = destination;
+
+        emit!(PayoutDestinationSetEvent {
+            group_name: group.name.clone(),
+            participant: participant.pubkey,
+            destination,
+        });
+
+        Ok(())
+    }
+
+    // Forward parts of this member's payout to other accounts, each share in
+    // basis points; the payout wallet keeps the rest. An empty list clears them.
+    pub fn set_payout_shares(
+        ctx: Context<SetPayoutDestination>,
+        shares: Vec<PayoutShare>,
+    ) -> Result<()> {
+        let group = &ctx.accounts.ajo_group;
+        let participant = &mut ctx.accounts.ajo_participant;
+
+        require!(!group.is_closed, KooPaaError::GroupAlreadyClosed);
+        require!(
+            group.payout_round <= participant.seat as u16,
+            KooPaaError::PayoutAlreadyReceived
+        );
+        validate_payout_shares(&shares)?;
+
+        participant.payout_shares = shares.clone();
+
+        emit!(PayoutSharesSetEvent {
+            group_name: group.name.clone(),
+            participant: participant.pubkey,
+            shares,
+        });
+
+        Ok(())
+    }
+
+    pub fn vacate_seat(ctx: Context<VacateSeat>) -> Result<()> {
+        let group = &mut ctx.accounts.ajo_group;
+        let participant = &mut ctx.accounts.ajo_participant;
+        let member = ctx.accounts.member.key();
+        let caller = ctx.accounts.caller.key();
+        let clock = Clock::get()?;
+
+        require!(!group.is_closed, KooPaaError::GroupAlreadyClosed);
+        let current_round =
+            current_contribution_round(group, &ctx.accounts.global_state, clock.unix_timestamp)
+                .ok_or(KooPaaError::GroupNotStarted)?;
+
+        require!(participant.is_active(), KooPaaError::NotParticipant);
+        require!(member != group.admin, KooPaaError::AdminCannotVacate);
+
+        // A member may leave on their own; the admin may only remove a member
+        // who has fallen behind on contributions
+        let defaulted = caller != member;
+        if defaulted {
+            require!(group.admin == caller, KooPaaError::OnlyAdminCanUpdate);
+            require!(
+                participant.contribution_round < current_round,
+                KooPaaError::ParticipantNotInArrears
+            );
+        } else if (participant.seat as u16) < group.payout_round {
+            // Members who already took their payout still owe the rest of the cycle
+            require!(
+                participant.contribution_round >= group.total_contribution_rounds(),
+                KooPaaError::OutstandingContributionsAfterPayout
+            );
+        }
+
+        vacate_participant(group, participant);
+
+        emit!(SeatVacatedEvent {
+            group_name: group.name.clone(),
+            participant: member,
+            seat_index: participant.seat,
+            defaulted,
+        });
+
+        Ok(())
+    }
+
+    pub fn fill_vacant_seat(ctx: Context<FillVacantSeat>) -> Result<()> {
+        let transfer = ContributionTransfer {
+            from: ctx.accounts.replacement_token_account.to_account_info(),
+            authority: ctx.accounts.replacement.to_account_info(),
+            vault: ctx.accounts.group_token_vault.to_account_info(),
+            treasury: ctx
+                .accounts
+                .treasury_token_account
+                .as_ref()
+                .map(|treasury| treasury.to_account_info()),
+            program: TransferProgram::Token {
+                mint: ctx.accounts.token_mint.to_account_info(),
+                decimals: ctx.accounts.token_mint.decimals,
+                token_program: ctx.accounts.token_program.to_account_info(),
+            },
+        };
+
+        fill_seat(
+            &mut ctx.accounts.ajo_group,
+            &ctx.accounts.global_state,
+            ctx.accounts.caller.key(),
+            ctx.accounts.proposal.as_mut(),
+            &mut ctx.accounts.vacated_participant,
+            &mut ctx.accounts.replacement_participant,
+            &transfer,
+            Clock::get()?.unix_timestamp,
+        )
+    }
+
+    // Native SOL groups: same rounds, payouts and refunds as token groups, but
+    // contributions are held as lamports on the group account itself
+
+    #[allow(clippy::too_many_arguments)]
+    pub fn create_native_ajo_group(
+        ctx: Context<CreateNativeAjoGroup>,
+        name: String,
+        contribution_amount: u64,
+        contribution_interval: u8,
+        payout_interval: u8,
+        num_participants: u8,
+        admin_commission: u8,
+        time_unit: TimeUnit,
+    ) -> Result<()> {
+        require!(
+            ctx.accounts
+                .global_state
+                .allowed_mints
+                .contains(&AjoGroup::NATIVE_MINT),
+            KooPaaError::MintNotAllowed
+        );
+
+        init_group(
+            &mut ctx.accounts.ajo_group,
+            &mut ctx.accounts.creator_participant,
+            ctx.bumps.creator_participant,
+            &mut ctx.accounts.global_state,
+            ctx.accounts.creator.key(),
+            name,
+            GroupTerms {
+                contribution_amount,
+                contribution_interval,
+                payout_interval,
+                time_unit,
+            },
+            num_participants,
+            admin_commission,
+            AjoGroup::NATIVE_MINT,
+            Clock::get()?.unix_timestamp,
         )?;
 
-        group.payout_round += 1;
+        let group = &mut ctx.accounts.ajo_group;
+        group.bumps = ctx.bumps.ajo_group;
+        group.vault_bump = 0; // No separate vault
+
+        Ok(())
+    }
+
+    pub fn contribute_native(ctx: Context<ContributeNative>, prepay_rounds: u16) -> Result<()> {
+        let transfer = ContributionTransfer {
+            from: ctx.accounts.contributor.to_account_info(),
+            authority: ctx.accounts.contributor.to_account_info(),
+            vault: ctx.accounts.ajo_group.to_account_info(),
+            treasury: ctx
+                .accounts
+                .treasury
+                .as_ref()
+                .map(|treasury| treasury.to_account_info()),
+            program: TransferProgram::System(ctx.accounts.system_program.to_account_info()),
+        };
+
+        settle_contribution(
+            &mut ctx.accounts.ajo_group,
+            &ctx.accounts.global_state,
+            &mut ctx.accounts.ajo_participant,
+            &transfer,
+            prepay_rounds,
+            Clock::get()?.unix_timestamp,
+        )
+    }
+
+    pub fn contribute_installment_native(
+        ctx: Context<ContributeNative>,
+        amount: u64,
+    ) -> Result<()> {
+        let transfer = ContributionTransfer {
+            from: ctx.accounts.contributor.to_account_info(),
+            authority: ctx.accounts.contributor.to_account_info(),
+            vault: ctx.accounts.ajo_group.to_account_info(),
+            treasury: ctx
+                .accounts
+                .treasury
+                .as_ref()
+                .map(|treasury| treasury.to_account_info()),
+            program: TransferProgram::System(ctx.accounts.system_program.to_account_info()),
+        };
+
+        settle_installment(
+            &mut ctx.accounts.ajo_group,
+            &ctx.accounts.global_state,
+            &mut ctx.accounts.ajo_participant,
+            &transfer,
+            amount,
+            Clock::get()?.unix_timestamp,
+        )
+    }
+
+    pub fn contribute_for_member_native(
+        ctx: Context<ContributeForMemberNative>,
+        prepay_rounds: u16,
+    ) -> Result<()> {
+        let transfer = ContributionTransfer {
+            from: ctx.accounts.payer.to_account_info(),
+            authority: ctx.accounts.payer.to_account_info(),
+            vault: ctx.accounts.ajo_group.to_account_info(),
+            treasury: ctx
+                .accounts
+                .treasury
+                .as_ref()
+                .map(|treasury| treasury.to_account_info()),
+            program: TransferProgram::System(ctx.accounts.system_program.to_account_info()),
+        };
+
+        settle_contribution(
+            &mut ctx.accounts.ajo_group,
+            &ctx.accounts.global_state,
+            &mut ctx.accounts.ajo_participant,
+            &transfer,
+            prepay_rounds,
+            Clock::get()?.unix_timestamp,
+        )
+    }
+
+    pub fn payout_native<'info>(
+        ctx: Context<'_, '_, 'info, 'info, PayoutNative<'info>>,
+    ) -> Result<()> {
+        let funds = GroupFunds::Lamports(ctx.accounts.ajo_group.to_account_info());
+        let group = &mut ctx.accounts.ajo_group;
+        let global_state = &ctx.accounts.global_state;
+        let recipient_participant = &ctx.accounts.recipient_participant;
+        let clock = Clock::get()?;
+
+        require!(!global_state.paused, KooPaaError::ProtocolPaused);
+        require!(group.paused_at.is_none(), KooPaaError::GroupPaused);
+
+        let caller = ctx.accounts.caller.key();
+        let split = payout_split(
+            group,
+            global_state,
+            recipient_participant,
+            clock.unix_timestamp,
+            caller != recipient_participant.pubkey && ctx.accounts.keeper.is_some(),
+        )?;
+
+        // `remaining_accounts` holds the payout share wallets
+        let share_transfers = payout_share_transfers(
+            &recipient_participant.payout_shares,
+            ctx.remaining_accounts,
+            split.payout_amount,
+        )?;
+
+        distribute_payout(
+            group,
+            &funds,
+            &split,
+            recipient_participant.pubkey,
+            &ctx.accounts.recipient.to_account_info(),
+            share_transfers,
+            ctx.remaining_accounts,
+            ctx.accounts
+                .treasury
+                .as_ref()
+                .map(|treasury| treasury.to_account_info()),
+            ctx.accounts
+                .keeper
+                .as_ref()
+                .map(|keeper| keeper.to_account_info()),
+            caller,
+        )
+    }
+
+    pub fn claim_refund_native(ctx: Context<ClaimRefundNative>) -> Result<()> {
+        let funds = GroupFunds::Lamports(ctx.accounts.ajo_group.to_account_info());
+        check_refunds_open(&ctx.accounts.ajo_group, &ctx.accounts.global_state)?;
+
+        pay_refund(
+            &ctx.accounts.ajo_group,
+            &mut ctx.accounts.ajo_participant,
+            &funds,
+            &ctx.accounts.participant.to_account_info(),
+        )
+    }
+
+    pub fn claim_admin_commission_native(ctx: Context<ClaimAdminCommissionNative>) -> Result<()> {
+        let funds = GroupFunds::Lamports(ctx.accounts.ajo_group.to_account_info());
+
+        pay_admin_commission(
+            &mut ctx.accounts.ajo_group,
+            ctx.accounts.admin.key(),
+            &funds,
+            &ctx.accounts.admin.to_account_info(),
+        )
+    }
+
+    pub fn fill_vacant_seat_native(ctx: Context<FillVacantSeatNative>) -> Result<()> {
+        let transfer = ContributionTransfer {
+            from: ctx.accounts.replacement.to_account_info(),
+            authority: ctx.accounts.replacement.to_account_info(),
+            vault: ctx.accounts.ajo_group.to_account_info(),
+            treasury: ctx
+                .accounts
+                .treasury
+                .as_ref()
+                .map(|treasury| treasury.to_account_info()),
+            program: TransferProgram::System(ctx.accounts.system_program.to_account_info()),
+        };
+
+        fill_seat(
+            &mut ctx.accounts.ajo_group,
+            &ctx.accounts.global_state,
+            ctx.accounts.caller.key(),
+            ctx.accounts.proposal.as_mut(),
+            &mut ctx.accounts.vacated_participant,
+            &mut ctx.accounts.replacement_participant,
+            &transfer,
+            Clock::get()?.unix_timestamp,
+        )
+    }
+}
+
+#[derive(Accounts)]
+pub struct Initialize<'info> {
+    #[account(
+        init,
+        payer = admin,
+        space = GlobalState::SIZE,
+        seeds = [b"global-state"],
+        bump
+    )]
+    pub global_state: Account<'info, GlobalState>,
+
+    #[account(mut)]
+    pub admin: Signer<'info>,
+
+    // Only the program's upgrade authority can claim protocol authority
+    #[account(constraint = program.programdata_address()? == Some(program_data.key()))]
+    pub program: Program<'info, crate::program::Koopa>,
+
+    #[account(
+        constraint = program_data.upgrade_authority_address == Some(admin.key())
+            @ KooPaaError::OnlyUpgradeAuthority
+    )]
+    pub program_data: Account<'info, ProgramData>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateProtocolConfig<'info> {
+    #[account(
+        mut,
+        seeds = [b"global-state"],
+        bump = global_state.bumps,
+        has_one = authority @ KooPaaError::OnlyProtocolAuthority
+    )]
+    pub global_state: Account<'info, GlobalState>,
+
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct AcceptAuthority<'info> {
+    #[account(
+        mut,
+        seeds = [b"global-state"],
+        bump = global_state.bumps
+    )]
+    pub global_state: Account<'info, GlobalState>,
+
+    pub new_authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct MigrateGlobalState<'info> {
+    /// CHECK: may still hold an old layout; validated and rewritten by the instruction
+    #[account(
+        mut,
+        owner = crate::ID,
+        seeds = [b"global-state"],
+        bump
+    )]
+    pub global_state: UncheckedAccount<'info>,
+
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    #[account(constraint = program.programdata_address()? == Some(program_data.key()))]
+    pub program: Program<'info, crate::program::Koopa>,
+
+    #[account(
+        constraint = program_data.upgrade_authority_address == Some(authority.key())
+            @ KooPaaError::OnlyUpgradeAuthority
+    )]
+    pub program_data: Account<'info, ProgramData>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct MigrateAjoGroup<'info> {
+    /// CHECK: may still hold an old layout; validated and rewritten by the instruction
+    #[account(mut, owner = crate::ID)]
+    pub ajo_group: UncheckedAccount<'info>,
+
+    #[account(
+        seeds = [b"global-state"],
+        bump = global_state.bumps
+    )]
+    pub global_state: Account<'info, GlobalState>,
+
+    #[account(
+        seeds = [b"group-vault", ajo_group.key().as_ref()],
+        bump
+    )]
+    pub group_token_vault: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(
+    name: String,
+    contribution_amount: u64,
+    contribution_interval: u8,
+    payout_interval: u8,
+    num_participants: u8
+)]
+pub struct CreateAjoGroup<'info> {
+    #[account(
+        init,
+        payer = creator,
+        space = AjoGroup::calculate_size(&name, num_participants),
+        seeds = [b"ajo-group", name.as_bytes()],
+        bump
+    )]
+    pub ajo_group: Account<'info, AjoGroup>,
+
+    #[account(mut)]
+    pub creator: Signer<'info>,
+
+    #[account(
+        init,
+        payer = creator,
+        space = AjoParticipant::SIZE,
+        seeds = [b"participant", ajo_group.key().as_ref(), creator.key().as_ref()],
+        bump
+    )]
+    pub creator_participant: Account<'info, AjoParticipant>,
+
+    #[account(
+        mut,
+        seeds = [b"global-state"],
+        bump = global_state.bumps
+    )]
+    pub global_state: Account<'info, GlobalState>,
+
+    pub token_mint: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        init,
+        payer = creator,
+        seeds = [b"group-vault", ajo_group.key().as_ref()],
+        bump,
+        token::mint = token_mint,
+        token::authority = ajo_group,
+        token::token_program = token_program
+    )]
+    pub group_token_vault: InterfaceAccount<'info, TokenAccount>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+    pub rent: Sysvar<'info, Rent>,
+}
+
+#[derive(Accounts)]
+#[instruction(
+    contribution_amount: u64,
+    contribution_interval: u8,
+    payout_interval: u8,
+    num_participants: u8
+)]
+pub struct UpdateAjoGroup<'info> {
+    #[account(
+        mut,
+        seeds = [b"ajo-group", ajo_group.name.as_bytes()],
+        bump = ajo_group.bumps,
+        realloc = AjoGroup::calculate_size(&ajo_group.name, num_participants),
+        realloc::payer = admin,
+        realloc::zero = false
+    )]
+    pub ajo_group: Account<'info, AjoGroup>,
+
+    #[account(mut)]
+    pub admin: Signer<'info>,
+
+    #[account(
+        seeds = [b"global-state"],
+        bump = global_state.bumps
+    )]
+    pub global_state: Account<'info, GlobalState>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct RequestJoinAjoGroup<'info> {
+    #[account(
+        mut,
+        seeds = [b"ajo-group", ajo_group.name.as_bytes()],
+        bump = ajo_group.bumps
+    )]
+    pub ajo_group: Account<'info, AjoGroup>,
+
+    #[account(mut)]
+    pub participant: Signer<'info>,
+
+    #[account(
+        init_if_needed,
+        payer = participant,
+        space = AjoParticipant::SIZE,
+        seeds = [b"participant", ajo_group.key().as_ref(), participant.key().as_ref()],
+        bump
+    )]
+    pub ajo_participant: Account<'info, AjoParticipant>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct Contribute<'info> {
+    #[account(
+        mut,
+        seeds = [b"ajo-group", ajo_group.name.as_bytes()],
+        bump = ajo_group.bumps
+    )]
+    pub ajo_group: Account<'info, AjoGroup>,
+
+    pub contributor: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"participant", ajo_group.key().as_ref(), contributor.key().as_ref()],
+        bump = ajo_participant.bumps,
+        constraint = ajo_participant.is_active() @ KooPaaError::NotParticipant
+    )]
+    pub ajo_participant: Account<'info, AjoParticipant>,
+
+    #[account(
+        mut,
+        constraint = contributor_token_account.owner == contributor.key(),
+        constraint = contributor_token_account.mint == token_mint.key(),
+    )]
+    pub contributor_token_account: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        seeds = [b"group-vault", ajo_group.key().as_ref()],
+        bump = ajo_group.vault_bump,
+        token::mint = token_mint,
+    )]
+    pub group_token_vault: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(address = ajo_group.mint @ KooPaaError::InvalidTokenAccountMint)]
+    pub token_mint: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        seeds = [b"global-state"],
+        bump = global_state.bumps
+    )]
+    pub global_state: Account<'info, GlobalState>,
+
+    /// Only required when the protocol charges a fee on contributions
+    #[account(
+        mut,
+        constraint = treasury_token_account.owner == global_state.config.treasury,
+        constraint = treasury_token_account.mint == token_mint.key(),
+    )]
+    pub treasury_token_account: Option<InterfaceAccount<'info, TokenAccount>>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct Payout<'info> {
+    #[account(
+        mut,
+        seeds = [b"ajo-group", ajo_group.name.as_bytes()],
+        bump = ajo_group.bumps
+    )]
+    pub ajo_group: Account<'info, AjoGroup>,
+
+    #[account(
+        mut,
+        seeds = [b"group-vault", ajo_group.key().as_ref()],
+        bump = ajo_group.vault_bump,
+        token::mint = token_mint,
+    )]
+    pub group_token_vault: InterfaceAccount<'info, TokenAccount>,
+
+    /// Token account of the recipient's payout wallet (does NOT have to sign)
+    #[account(mut)]
+    pub recipient: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        seeds = [
+            b"participant",
+            ajo_group.key().as_ref(),
+            recipient_participant.pubkey.as_ref()
+        ],
+        bump = recipient_participant.bumps
+    )]
+    pub recipient_participant: Account<'info, AjoParticipant>,
+
+    pub caller: Signer<'info>,
+
+    #[account(
+        seeds = [b"global-state"],
+        bump = global_state.bumps
+    )]
+    pub global_state: Account<'info, GlobalState>,
+
+    /// Only required when the protocol charges a fee on payouts
+    #[account(
+        mut,
+        constraint = treasury_token_account.owner == global_state.config.treasury,
+        constraint = treasury_token_account.mint == token_mint.key(),
+    )]
+    pub treasury_token_account: Option<InterfaceAccount<'info, TokenAccount>>,
+
+    /// Caller's token account for the keeper tip, omit to waive it
+    #[account(
+        mut,
+        constraint = keeper_token_account.owner == caller.key(),
+        constraint = keeper_token_account.mint == token_mint.key(),
+    )]
+    pub keeper_token_account: Option<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(address = ajo_group.mint @ KooPaaError::InvalidTokenAccountMint)]
+    pub token_mint: InterfaceAccount<'info, Mint>,
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+#[derive(Accounts)]
+pub struct WithdrawJoinRequest<'info> {
+    #[account(
+        mut,
+        seeds = [b"ajo-group", ajo_group.name.as_bytes()],
+        bump = ajo_group.bumps
+    )]
+    pub ajo_group: Account<'info, AjoGroup>,
+
+    #[account(mut)]
+    pub participant: Signer<'info>,
+
+    #[account(
+        mut,
+        close = participant,
+        seeds = [b"participant", ajo_group.key().as_ref(), participant.key().as_ref()],
+        bump = ajo_participant.bumps,
+        constraint = ajo_participant.status == ParticipantStatus::Pending @ KooPaaError::NoJoinRequest
+    )]
+    pub ajo_participant: Account<'info, AjoParticipant>,
+}
+
+#[derive(Accounts)]
+pub struct ApproveJoinRequest<'info> {
+    #[account(
+        mut,
+        seeds = [b"ajo-group", ajo_group.name.as_bytes()],
+        bump = ajo_group.bumps
+    )]
+    pub ajo_group: Account<'info, AjoGroup>,
+
+    /// CHECK: we only care about the pubkey; refunded the record rent on rejection
+    #[account(mut)]
+    pub participant: UncheckedAccount<'info>,
+
+    #[account(
+        init_if_needed,
+        payer = caller,
+        space = AjoParticipant::SIZE,
+        seeds = [b"participant", ajo_group.key().as_ref(), participant.key().as_ref()],
+        bump
+    )]
+    pub ajo_participant: Account<'info, AjoParticipant>,
+
+    #[account(mut)]
+    pub caller: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"global-state"],
+        bump = global_state.bumps
+    )]
+    pub global_state: Account<'info, GlobalState>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct CloseAjoGroup<'info> {
+    #[account(
+        mut,
+        seeds = [b"ajo-group", ajo_group.name.as_bytes()],
+        bump = ajo_group.bumps
+    )]
+    pub ajo_group: Account<'info, AjoGroup>,
+
+    pub participant: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"participant", ajo_group.key().as_ref(), participant.key().as_ref()],
+        bump = ajo_participant.bumps,
+        constraint = ajo_participant.is_active() @ KooPaaError::NotParticipant
+    )]
+    pub ajo_participant: Account<'info, AjoParticipant>,
+
+    #[account(
+        mut,
+        seeds = [b"global-state"],
+        bump = global_state.bumps
+    )]
+    pub global_state: Account<'info, GlobalState>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct VoteGroupPause<'info> {
+    #[account(
+        mut,
+        seeds = [b"ajo-group", ajo_group.name.as_bytes()],
+        bump = ajo_group.bumps
+    )]
+    pub ajo_group: Account<'info, AjoGroup>,
+
+    pub participant: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"participant", ajo_group.key().as_ref(), participant.key().as_ref()],
+        bump = ajo_participant.bumps,
+        constraint = ajo_participant.is_active() @ KooPaaError::NotParticipant
+    )]
+    pub ajo_participant: Account<'info, AjoParticipant>,
+
+    #[account(
+        seeds = [b"global-state"],
+        bump = global_state.bumps
+    )]
+    pub global_state: Account<'info, GlobalState>,
+}
+
+#[derive(Accounts)]
+pub struct ClaimRefund<'info> {
+    #[account(
+        mut,
+        seeds = [b"ajo-group", ajo_group.name.as_bytes()],
+        bump = ajo_group.bumps
+    )]
+    pub ajo_group: Account<'info, AjoGroup>,
+
+    #[account(
+        mut,
+        seeds = [b"group-vault", ajo_group.key().as_ref()],
+        bump = ajo_group.vault_bump,
+        token::mint = token_mint,
+    )]
+    pub group_token_vault: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(mut)]
+    pub participant: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"participant", ajo_group.key().as_ref(), participant.key().as_ref()],
+        bump = ajo_participant.bumps
+    )]
+    pub ajo_participant: Account<'info, AjoParticipant>,
+
+    #[account(
+        mut,
+        constraint = participant_token_account.owner == participant.key(),
+        constraint = participant_token_account.mint == token_mint.key(),
+    )]
+    pub participant_token_account: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        seeds = [b"global-state"],
+        bump = global_state.bumps
+    )]
+    pub global_state: Account<'info, GlobalState>,
+
+    #[account(address = ajo_group.mint @ KooPaaError::InvalidTokenAccountMint)]
+    pub token_mint: InterfaceAccount<'info, Mint>,
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+#[derive(Accounts)]
+pub struct VacateSeat<'info> {
+    #[account(
+        mut,
+        seeds = [b"ajo-group", ajo_group.name.as_bytes()],
+        bump = ajo_group.bumps
+    )]
+    pub ajo_group: Account<'info, AjoGroup>,
+
+    /// CHECK: we only care about the pubkey
+    pub member: UncheckedAccount<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"participant", ajo_group.key().as_ref(), member.key().as_ref()],
+        bump = ajo_participant.bumps
+    )]
+    pub ajo_participant: Account<'info, AjoParticipant>,
+
+    pub caller: Signer<'info>,
+
+    #[account(
+        seeds = [b"global-state"],
+        bump = global_state.bumps
+    )]
+    pub global_state: Account<'info, GlobalState>,
+}
+
+#[derive(Accounts)]
+pub struct FillVacantSeat<'info> {
+    #[account(
+        mut,
+        seeds = [b"ajo-group", ajo_group.name.as_bytes()],
+        bump = ajo_group.bumps
+    )]
+    pub ajo_group: Account<'info, AjoGroup>,
+
+    #[account(
+        mut,
+        seeds = [b"group-vault", ajo_group.key().as_ref()],
+        bump = ajo_group.vault_bump,
+        token::mint = token_mint,
+    )]
+    pub group_token_vault: InterfaceAccount<'info, TokenAccount>,
+
+    pub caller: Signer<'info>,
+
+    /// Approved `FillSeat` proposal, when the caller is not the admin
+    #[account(mut)]
+    pub proposal: Option<Account<'info, Proposal>>,
+
+    #[account(
+        mut,
+        seeds = [
+            b"participant",
+            ajo_group.key().as_ref(),
+            vacated_participant.pubkey.as_ref()
+        ],
+        bump = vacated_participant.bumps
+    )]
+    pub vacated_participant: Account<'info, AjoParticipant>,
+
+    pub replacement: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"participant", ajo_group.key().as_ref(), replacement.key().as_ref()],
+        bump = replacement_participant.bumps
+    )]
+    pub replacement_participant: Account<'info, AjoParticipant>,
+
+    #[account(
+        seeds = [b"global-state"],
+        bump = global_state.bumps
+    )]
+    pub global_state: Account<'info, GlobalState>,
+
+    #[account(
+        mut,
+        constraint = replacement_token_account.owner == replacement.key(),
+        constraint = replacement_token_account.mint == token_mint.key(),
+    )]
+    pub replacement_token_account: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(address = ajo_group.mint @ KooPaaError::InvalidTokenAccountMint)]
+    pub token_mint: InterfaceAccount<'info, Mint>,
+
+    /// Only required when the protocol charges a fee on contributions
+    #[account(
+        mut,
+        constraint = treasury_token_account.owner == global_state.config.treasury,
+        constraint = treasury_token_account.mint == token_mint.key(),
+    )]
+    pub treasury_token_account: Option<InterfaceAccount<'info, TokenAccount>>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+#[derive(Accounts)]
+pub struct ClaimAdminCommission<'info> {
+    #[account(
+        mut,
+        seeds = [b"ajo-group", ajo_group.name.as_bytes()],
+        bump = ajo_group.bumps
+    )]
+    pub ajo_group: Account<'info, AjoGroup>,
+
+    #[account(
+        mut,
+        seeds = [b"group-vault", ajo_group.key().as_ref()],
+        bump = ajo_group.vault_bump,
+        token::mint = token_mint,
+    )]
+    pub group_token_vault: InterfaceAccount<'info, TokenAccount>,
+
+    pub admin: Signer<'info>,
+
+    #[account(
+        mut,
+        constraint = admin_token_account.owner == admin.key(),
+        constraint = admin_token_account.mint == token_mint.key(),
+    )]
+    pub admin_token_account: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(address = ajo_group.mint @ KooPaaError::InvalidTokenAccountMint)]
+    pub token_mint: InterfaceAccount<'info, Mint>,
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+#[derive(Accounts)]
+#[instruction(
+    name: String,
+    contribution_amount: u64,
+    contribution_interval: u8,
+    payout_interval: u8,
+    num_participants: u8
+)]
+pub struct CreateNativeAjoGroup<'info> {
+    #[account(
+        init,
+        payer = creator,
+        space = AjoGroup::calculate_size(&name, num_participants),
+        seeds = [b"ajo-group", name.as_bytes()],
+        bump
+    )]
+    pub ajo_group: Account<'info, AjoGroup>,
+
+    #[account(mut)]
+    pub creator: Signer<'info>,
+
+    #[account(
+        init,
+        payer = creator,
+        space = AjoParticipant::SIZE,
+        seeds = [b"participant", ajo_group.key().as_ref(), creator.key().as_ref()],
+        bump
+    )]
+    pub creator_participant: Account<'info, AjoParticipant>,
+
+    #[account(
+        mut,
+        seeds = [b"global-state"],
+        bump = global_state.bumps
+    )]
+    pub global_state: Account<'info, GlobalState>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ContributeNative<'info> {
+    #[account(
+        mut,
+        seeds = [b"ajo-group", ajo_group.name.as_bytes()],
+        bump = ajo_group.bumps,
+        constraint = ajo_group.is_native() @ KooPaaError::NotNativeSolGroup
+    )]
+    pub ajo_group: Account<'info, AjoGroup>,
+
+    #[account(mut)]
+    pub contributor: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"participant", ajo_group.key().as_ref(), contributor.key().as_ref()],
+        bump = ajo_participant.bumps,
+        constraint = ajo_participant.is_active() @ KooPaaError::NotParticipant
+    )]
+    pub ajo_participant: Account<'info, AjoParticipant>,
+
+    #[account(
+        seeds = [b"global-state"],
+        bump = global_state.bumps
+    )]
+    pub global_state: Account<'info, GlobalState>,
+
+    /// CHECK: protocol treasury wallet, only required when the protocol charges
+    /// a fee on contributions
+    #[account(mut, address = global_state.config.treasury)]
+    pub treasury: Option<UncheckedAccount<'info>>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct PayoutNative<'info> {
+    #[account(
+        mut,
+        seeds = [b"ajo-group", ajo_group.name.as_bytes()],
+        bump = ajo_group.bumps,
+        constraint = ajo_group.is_native() @ KooPaaError::NotNativeSolGroup
+    )]
+    pub ajo_group: Account<'info, AjoGroup>,
+
+    /// CHECK: payout wallet of the member whose turn it is (does NOT have to sign)
+    #[account(
+        mut,
+        address = recipient_participant.payout_wallet() @ KooPaaError::NotCurrentRecipient
+    )]
+    pub recipient: UncheckedAccount<'info>,
+
+    #[account(
+        seeds = [
+            b"participant",
+            ajo_group.key().as_ref(),
+            recipient_participant.pubkey.as_ref()
+        ],
+        bump = recipient_participant.bumps
+    )]
+    pub recipient_participant: Account<'info, AjoParticipant>,
+
+    pub caller: Signer<'info>,
+
+    /// CHECK: caller's wallet for the keeper tip, omit to waive it
+    #[account(mut, address = caller.key())]
+    pub keeper: Option<UncheckedAccount<'info>>,
+
+    #[account(
+        seeds = [b"global-state"],
+        bump = global_state.bumps
+    )]
+    pub global_state: Account<'info, GlobalState>,
+
+    /// CHECK: protocol treasury wallet, only required when the protocol charges
+    /// a fee on payouts
+    #[account(mut, address = global_state.config.treasury)]
+    pub treasury: Option<UncheckedAccount<'info>>,
+}
+
+#[derive(Accounts)]
+pub struct ClaimRefundNative<'info> {
+    #[account(
+        mut,
+        seeds = [b"ajo-group", ajo_group.name.as_bytes()],
+        bump = ajo_group.bumps,
+        constraint = ajo_group.is_native() @ KooPaaError::NotNativeSolGroup
+    )]
+    pub ajo_group: Account<'info, AjoGroup>,
+
+    #[account(mut)]
+    pub participant: Signer<'info>,
 
-        emit!(PayoutMadeEvent {
-            group_name,
-            recipient: recipient_pubkey,
-            payout_amount,
-            payout_round: group.payout_round,
-        });
+    #[account(
+        mut,
+        seeds = [b"participant", ajo_group.key().as_ref(), participant.key().as_ref()],
+        bump = ajo_participant.bumps
+    )]
+    pub ajo_participant: Account<'info, AjoParticipant>,
 
-        Ok(())
-    }
+    #[account(
+        seeds = [b"global-state"],
+        bump = global_state.bumps
+    )]
+    pub global_state: Account<'info, GlobalState>,
+}
 
-    pub fn close_ajo_group(ctx: Context<CloseAjoGroup>) -> Result<()> {
-        let group = &mut ctx.accounts.ajo_group;
-        let participant = &ctx.accounts.participant;
-        let global_state = &mut ctx.accounts.global_state;
+#[derive(Accounts)]
+pub struct ClaimAdminCommissionNative<'info> {
+    #[account(
+        mut,
+        seeds = [b"ajo-group", ajo_group.name.as_bytes()],
+        bump = ajo_group.bumps,
+        constraint = ajo_group.is_native() @ KooPaaError::NotNativeSolGroup
+    )]
+    pub ajo_group: Account<'info, AjoGroup>,
 
-        if group.is_closed {
-            return err!(KooPaaError::GroupAlreadyClosed);
-        }
+    #[account(mut)]
+    pub admin: Signer<'info>,
+}
 
-        let is_participant = group
-            .participants
-            .iter()
-            .any(|p| p.pubkey == participant.key());
+#[derive(Accounts)]
+pub struct FillVacantSeatNative<'info> {
+    #[account(
+        mut,
+        seeds = [b"ajo-group", ajo_group.name.as_bytes()],
+        bump = ajo_group.bumps,
+        constraint = ajo_group.is_native() @ KooPaaError::NotNativeSolGroup
+    )]
+    pub ajo_group: Account<'info, AjoGroup>,
 
-        require!(is_participant, KooPaaError::NotParticipant);
+    pub caller: Signer<'info>,
 
-        let already_voted = group.close_votes.contains(&participant.key());
-        require!(!already_voted, KooPaaError::AlreadyVotedToClose);
+    /// Approved `FillSeat` proposal, when the caller is not the admin
+    #[account(mut)]
+    pub proposal: Option<Account<'info, Proposal>>,
 
-        group.close_votes.push(participant.key());
+    #[account(
+        mut,
+        seeds = [
+            b"participant",
+            ajo_group.key().as_ref(),
+            vacated_participant.pubkey.as_ref()
+        ],
+        bump = vacated_participant.bumps
+    )]
+    pub vacated_participant: Account<'info, AjoParticipant>,
 
-        let total_participants = group.participants.len();
-        let total_votes = group.close_votes.len();
+    #[account(mut)]
+    pub replacement: Signer<'info>,
 
-        let group_started = group.start_timestamp.is_some();
-        let group_contribution_amount = group.contribution_amount;
+    #[account(
+        mut,
+        seeds = [b"participant", ajo_group.key().as_ref(), replacement.key().as_ref()],
+        bump = replacement_participant.bumps
+    )]
+    pub replacement_participant: Account<'info, AjoParticipant>,
 
-        if total_votes * 2 > total_participants {
-            let minimum_common_contribution_round = group
-                .participants
-                .iter()
-                .map(|p| p.contribution_round)
-                .min()
-                .unwrap();
+    #[account(
+        seeds = [b"global-state"],
+        bump = global_state.bumps
+    )]
+    pub global_state: Account<'info, GlobalState>,
 
-            for participant in group.participants.iter_mut() {
-                participant.refund_amount = if group_started {
-                    let refundable_rounds =
-                        participant.contribution_round - minimum_common_contribution_round;
-                    group_contribution_amount * refundable_rounds as u64
-                } else {
-                    0
-                };
-            }
+    /// CHECK: protocol treasury wallet, only required when the protocol charges
+    /// a fee on contributions
+    #[account(mut, address = global_state.config.treasury)]
+    pub treasury: Option<UncheckedAccount<'info>>,
 
-            if group_started && global_state.active_groups > 0 {
-                global_state.active_groups -= 1;
-            }
-            group.is_closed = true;
+    pub system_program: Program<'info, System>,
+}
 
-            emit!(AjoGroupClosedEvent {
-                group_name: group.name.clone(),
-                total_votes: total_votes as u8,
-                group_size: total_participants as u8,
-            });
-        }
+#[derive(Accounts)]
+pub struct DepositIdleFunds<'info> {
+    #[account(
+        mut,
+        seeds = [b"ajo-group", ajo_group.name.as_bytes()],
+        bump = ajo_group.bumps
+    )]
+    pub ajo_group: Account<'info, AjoGroup>,
 
-        Ok(())
-    }
+    #[account(
+        mut,
+        seeds = [b"group-vault", ajo_group.key().as_ref()],
+        bump = ajo_group.vault_bump,
+        token::mint = token_mint,
+    )]
+    pub group_token_vault: InterfaceAccount<'info, TokenAccount>,
 
-    pub fn claim_refund(ctx: Context<ClaimRefund>) -> Result<()> {
-        let authority_info = ctx.accounts.ajo_group.to_account_info();
-        let group = &mut ctx.accounts.ajo_group;
-        let participant_key = ctx.accounts.participant.key();
+    // The group opts into an adapter by vote; the admin decides what to park
+    pub admin: Signer<'info>,
 
-        require!(group.is_closed, KooPaaError::GroupNotClosed);
+    #[account(
+        seeds = [b"global-state"],
+        bump = global_state.bumps
+    )]
+    pub global_state: Account<'info, GlobalState>,
 
-        let participant_index = group
-            .participants
-            .iter()
-            .position(|p| p.pubkey == participant_key)
-            .ok_or(KooPaaError::NotParticipant)?;
+    #[account(address = ajo_group.mint @ KooPaaError::InvalidTokenAccountMint)]
+    pub token_mint: InterfaceAccount<'info, Mint>,
+    pub token_program: Interface<'info, TokenInterface>,
+}
 
-        let refund_amount = group.participants[participant_index].refund_amount;
-        require!(refund_amount > 0, KooPaaError::NoRefundToClaim);
+#[derive(Accounts)]
+pub struct ContributeForMember<'info> {
+    #[account(
+        mut,
+        seeds = [b"ajo-group", ajo_group.name.as_bytes()],
+        bump = ajo_group.bumps
+    )]
+    pub ajo_group: Account<'info, AjoGroup>,
 
-        let transfer_accounts = Transfer {
-            from: ctx.accounts.group_token_vault.to_account_info(),
-            to: ctx.accounts.participant_token_account.to_account_info(),
-            authority: authority_info,
-        };
+    pub payer: Signer<'info>,
 
-        let group_name = group.name.clone();
-        let signer_seeds = &[b"ajo-group", group_name.as_bytes(), &[group.bumps]];
+    /// CHECK: only used to derive the participant PDA of the member being credited
+    pub beneficiary: UncheckedAccount<'info>,
 
-        transfer(
-            CpiContext::new_with_signer(
-                ctx.accounts.token_program.to_account_info(),
-                transfer_accounts,
-                &[signer_seeds],
-            ),
-            refund_amount,
-        )?;
+    #[account(
+        mut,
+        seeds = [b"participant", ajo_group.key().as_ref(), beneficiary.key().as_ref()],
+        bump = ajo_participant.bumps,
+        constraint = ajo_participant.is_active() @ KooPaaError::NotParticipant
+    )]
+    pub ajo_participant: Account<'info, AjoParticipant>,
 
-        // Mark refund claimed
-        group.participants[participant_index].refund_amount = 0;
+    #[account(
+        mut,
+        constraint = payer_token_account.owner == payer.key(),
+        constraint = payer_token_account.mint == token_mint.key(),
+    )]
+    pub payer_token_account: InterfaceAccount<'info, TokenAccount>,
 
-        emit!(RefundClaimedEvent {
-            group_name: group.name.clone(),
-            participant: participant_key,
-            amount: refund_amount,
-        });
+    #[account(
+        mut,
+        seeds = [b"group-vault", ajo_group.key().as_ref()],
+        bump = ajo_group.vault_bump,
+        token::mint = token_mint,
+    )]
+    pub group_token_vault: InterfaceAccount<'info, TokenAccount>,
 
-        Ok(())
-    }
-}
+    #[account(address = ajo_group.mint @ KooPaaError::InvalidTokenAccountMint)]
+    pub token_mint: InterfaceAccount<'info, Mint>,
 
-#[derive(Accounts)]
-pub struct Initialize<'info> {
     #[account(
-        init,
-        payer = admin,
-        space = GlobalState::SIZE,
         seeds = [b"global-state"],
-        bump
+        bump = global_state.bumps
     )]
     pub global_state: Account<'info, GlobalState>,
 
-    #[account(mut)]
-    pub admin: Signer<'info>,
+    /// Only required when the protocol charges a fee on contributions
+    #[account(
+        mut,
+        constraint = treasury_token_account.owner == global_state.config.treasury,
+        constraint = treasury_token_account.mint == token_mint.key(),
+    )]
+    pub treasury_token_account: Option<InterfaceAccount<'info, TokenAccount>>,
 
+    pub token_program: Interface<'info, TokenInterface>,
     pub system_program: Program<'info, System>,
 }
 
 #[derive(Accounts)]
-#[instruction(
-    name: String,
-    security_deposit: u64,
-    contribution_amount: u64,
-    contribution_interval: u8,
-    payout_interval: u8,
-    num_participants: u8
-)]
-pub struct CreateAjoGroup<'info> {
+pub struct ContributeForMemberNative<'info> {
     #[account(
-        init,
-        payer = creator,
-        space = AjoGroup::calculate_size(&name, num_participants),
-        seeds = [b"ajo-group", name.as_bytes()],
-        bump
+        mut,
+        seeds = [b"ajo-group", ajo_group.name.as_bytes()],
+        bump = ajo_group.bumps,
+        constraint = ajo_group.is_native() @ KooPaaError::NotNativeSolGroup
     )]
     pub ajo_group: Account<'info, AjoGroup>,
 
     #[account(mut)]
-    pub creator: Signer<'info>,
+    pub payer: Signer<'info>,
+
+    /// CHECK: only used to derive the participant PDA of the member being credited
+    pub beneficiary: UncheckedAccount<'info>,
 
     #[account(
         mut,
+        seeds = [b"participant", ajo_group.key().as_ref(), beneficiary.key().as_ref()],
+        bump = ajo_participant.bumps,
+        constraint = ajo_participant.is_active() @ KooPaaError::NotParticipant
+    )]
+    pub ajo_participant: Account<'info, AjoParticipant>,
+
+    #[account(
         seeds = [b"global-state"],
         bump = global_state.bumps
     )]
     pub global_state: Account<'info, GlobalState>,
 
-    pub token_mint: Account<'info, Mint>,
-
-    #[account(
-        init,
-        payer = creator,
-        seeds = [b"group-vault", ajo_group.key().as_ref()],
-        bump,
-        token::mint = token_mint,
-        token::authority = ajo_group
-    )]
-    pub group_token_vault: Account<'info, TokenAccount>,
+    /// CHECK: protocol treasury wallet, only required when the protocol charges
+    /// a fee on contributions
+    #[account(mut, address = global_state.config.treasury)]
+    pub treasury: Option<UncheckedAccount<'info>>,
 
-    pub token_program: Program<'info, Token>,
     pub system_program: Program<'info, System>,
-    pub rent: Sysvar<'info, Rent>,
 }
 
 #[derive(Accounts)]
-pub struct RequestJoinAjoGroup<'info> {
+pub struct UpdateAutoDebit<'info> {
     #[account(
-        mut,
         seeds = [b"ajo-group", ajo_group.name.as_bytes()],
         bump = ajo_group.bumps
     )]
@@ -526,12 +2784,27 @@ pub struct RequestJoinAjoGroup<'info> {
 
     pub participant: Signer<'info>,
 
-    pub token_program: Program<'info, Token>,
-    pub system_program: Program<'info, System>,
+    #[account(
+        seeds = [b"participant", ajo_group.key().as_ref(), participant.key().as_ref()],
+        bump = ajo_participant.bumps,
+        constraint = ajo_participant.is_active() @ KooPaaError::NotParticipant
+    )]
+    pub ajo_participant: Account<'info, AjoParticipant>,
+
+    #[account(
+        mut,
+        constraint = participant_token_account.owner == participant.key(),
+        constraint = participant_token_account.mint == token_mint.key(),
+    )]
+    pub participant_token_account: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(address = ajo_group.mint @ KooPaaError::InvalidTokenAccountMint)]
+    pub token_mint: InterfaceAccount<'info, Mint>,
+    pub token_program: Interface<'info, TokenInterface>,
 }
 
 #[derive(Accounts)]
-pub struct Contribute<'info> {
+pub struct CollectContribution<'info> {
     #[account(
         mut,
         seeds = [b"ajo-group", ajo_group.name.as_bytes()],
@@ -539,56 +2812,79 @@ pub struct Contribute<'info> {
     )]
     pub ajo_group: Account<'info, AjoGroup>,
 
-    pub contributor: Signer<'info>,
+    pub caller: Signer<'info>,
 
     #[account(
         mut,
-        constraint = contributor_token_account.owner == contributor.key(),
-        constraint = contributor_token_account.mint == token_mint.key(),
+        seeds = [b"participant", ajo_group.key().as_ref(), participant_token_account.owner.as_ref()],
+        bump = ajo_participant.bumps,
+        constraint = ajo_participant.is_active() @ KooPaaError::NotParticipant
+    )]
+    pub ajo_participant: Account<'info, AjoParticipant>,
+
+    #[account(
+        mut,
+        constraint = participant_token_account.mint == token_mint.key(),
     )]
-    pub contributor_token_account: Account<'info, TokenAccount>,
+    pub participant_token_account: InterfaceAccount<'info, TokenAccount>,
 
     #[account(
         mut,
         seeds = [b"group-vault", ajo_group.key().as_ref()],
         bump = ajo_group.vault_bump,
+        token::mint = token_mint,
     )]
-    pub group_token_vault: Account<'info, TokenAccount>,
+    pub group_token_vault: InterfaceAccount<'info, TokenAccount>,
 
-    pub token_mint: Account<'info, Mint>,
+    #[account(address = ajo_group.mint @ KooPaaError::InvalidTokenAccountMint)]
+    pub token_mint: InterfaceAccount<'info, Mint>,
 
-    pub token_program: Program<'info, Token>,
-    pub system_program: Program<'info, System>,
+    #[account(
+        seeds = [b"global-state"],
+        bump = global_state.bumps
+    )]
+    pub global_state: Account<'info, GlobalState>,
+
+    /// Only required when the protocol charges a fee on contributions
+    #[account(
+        mut,
+        constraint = treasury_token_account.owner == global_state.config.treasury,
+        constraint = treasury_token_account.mint == token_mint.key(),
+    )]
+    pub treasury_token_account: Option<InterfaceAccount<'info, TokenAccount>>,
+
+    /// Caller's token account for the keeper tip, omit to waive it
+    #[account(
+        mut,
+        constraint = keeper_token_account.owner == caller.key(),
+        constraint = keeper_token_account.mint == token_mint.key(),
+    )]
+    pub keeper_token_account: Option<InterfaceAccount<'info, TokenAccount>>,
+
+    pub token_program: Interface<'info, TokenInterface>,
 }
 
 #[derive(Accounts)]
-pub struct Payout<'info> {
+pub struct SetPayoutDestination<'info> {
     #[account(
-        mut,
         seeds = [b"ajo-group", ajo_group.name.as_bytes()],
         bump = ajo_group.bumps
     )]
     pub ajo_group: Account<'info, AjoGroup>,
 
+    pub participant: Signer<'info>,
+
     #[account(
         mut,
-        seeds = [b"group-vault", ajo_group.key().as_ref()],
-        bump = ajo_group.vault_bump,
+        seeds = [b"participant", ajo_group.key().as_ref(), participant.key().as_ref()],
+        bump = ajo_participant.bumps,
+        constraint = ajo_participant.is_active() @ KooPaaError::NotParticipant
     )]
-    pub group_token_vault: Account<'info, TokenAccount>,
-
-    /// The recipient who will receive tokens (does NOT have to sign)
-    #[account(mut)]
-    pub recipient: Account<'info, TokenAccount>,
-
-    pub caller: Signer<'info>,
-
-    pub token_mint: Account<'info, Mint>,
-    pub token_program: Program<'info, Token>,
+    pub ajo_participant: Account<'info, AjoParticipant>,
 }
 
 #[derive(Accounts)]
-pub struct ApproveJoinRequest<'info>{
+pub struct CreateProposal<'info> {
     #[account(
         mut,
         seeds = [b"ajo-group", ajo_group.name.as_bytes()],
@@ -596,42 +2892,77 @@ pub struct ApproveJoinRequest<'info>{
     )]
     pub ajo_group: Account<'info, AjoGroup>,
 
-    /// CHECK: we only care about the pubkey
-    pub participant: UncheckedAccount<'info>,
+    #[account(mut)]
+    pub proposer: Signer<'info>,
+
+    #[account(
+        seeds = [b"participant", ajo_group.key().as_ref(), proposer.key().as_ref()],
+        bump = proposer_participant.bumps,
+        constraint = proposer_participant.is_active() @ KooPaaError::NotParticipant
+    )]
+    pub proposer_participant: Account<'info, AjoParticipant>,
 
-    pub caller: Signer<'info>,
-    
     #[account(
-        mut,
         seeds = [b"global-state"],
         bump = global_state.bumps
     )]
     pub global_state: Account<'info, GlobalState>,
+
+    #[account(
+        init,
+        payer = proposer,
+        space = Proposal::SIZE,
+        seeds = [
+            b"proposal",
+            ajo_group.key().as_ref(),
+            &ajo_group.proposal_count.to_le_bytes()
+        ],
+        bump
+    )]
+    pub proposal: Account<'info, Proposal>,
+
+    pub system_program: Program<'info, System>,
 }
 
 #[derive(Accounts)]
-pub struct CloseAjoGroup<'info> {
+pub struct VoteOnProposal<'info> {
     #[account(
-        mut,
         seeds = [b"ajo-group", ajo_group.name.as_bytes()],
         bump = ajo_group.bumps
     )]
     pub ajo_group: Account<'info, AjoGroup>,
 
-    pub participant: Signer<'info>,
+    #[account(mut)]
+    pub voter: Signer<'info>,
+
+    #[account(
+        seeds = [b"participant", ajo_group.key().as_ref(), voter.key().as_ref()],
+        bump = voter_participant.bumps,
+        constraint = voter_participant.is_active() @ KooPaaError::NotParticipant
+    )]
+    pub voter_participant: Account<'info, AjoParticipant>,
 
     #[account(
         mut,
-        seeds = [b"global-state"],
-        bump = global_state.bumps
+        seeds = [b"proposal", ajo_group.key().as_ref(), &proposal.id.to_le_bytes()],
+        bump = proposal.bumps
     )]
-    pub global_state: Account<'info, GlobalState>,
+    pub proposal: Account<'info, Proposal>,
+
+    #[account(
+        init,
+        payer = voter,
+        space = ProposalVote::SIZE,
+        seeds = [b"proposal-vote", proposal.key().as_ref(), voter.key().as_ref()],
+        bump
+    )]
+    pub vote: Account<'info, ProposalVote>,
 
     pub system_program: Program<'info, System>,
 }
 
 #[derive(Accounts)]
-pub struct ClaimRefund<'info> {
+pub struct ExecuteProposal<'info> {
     #[account(
         mut,
         seeds = [b"ajo-group", ajo_group.name.as_bytes()],
@@ -641,21 +2972,24 @@ pub struct ClaimRefund<'info> {
 
     #[account(
         mut,
-        seeds = [b"group-vault", ajo_group.key().as_ref()],
-        bump = ajo_group.vault_bump,
+        seeds = [b"proposal", ajo_group.key().as_ref(), &proposal.id.to_le_bytes()],
+        bump = proposal.bumps
     )]
-    pub group_token_vault: Account<'info, TokenAccount>,
-
-    #[account(mut)]
-    pub participant: Signer<'info>,
+    pub proposal: Account<'info, Proposal>,
 
     #[account(
-        mut,
-        constraint = participant_token_account.owner == participant.key(),
-        constraint = participant_token_account.mint == token_mint.key(),
+        seeds = [b"global-state"],
+        bump = global_state.bumps
     )]
-    pub participant_token_account: Account<'info, TokenAccount>,
+    pub global_state: Account<'info, GlobalState>,
+
+    /// Member the proposal acts on, when it acts on one
+    #[account(mut)]
+    pub member_participant: Option<Account<'info, AjoParticipant>>,
+
+    /// Holder of the next payout slot, for hardship payouts
+    #[account(mut)]
+    pub displaced_participant: Option<Account<'info, AjoParticipant>>,
 
-    pub token_mint: Account<'info, Mint>,
-    pub token_program: Program<'info, Token>,
+    pub caller: Signer<'info>,
 }
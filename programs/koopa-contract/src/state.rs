@@ -1,72 +1,1284 @@
+use crate::errors::KooPaaError;
 use anchor_lang::prelude::*;
 
-#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq)]
+pub enum ParticipantStatus {
+    Pending, // In the waiting room, or on standby once the group has started
+    Active,  // Holds a seat
+    Vacant,  // Left or removed after start; the seat waits for a replacement
+    Left,    // Seat handed over to a replacement
+}
+
+// Per-member state, one account per group and wallet
+#[account]
 pub struct AjoParticipant {
-    pub pubkey: Pubkey,
+    pub version: u8,
+    pub group: Pubkey,  // Group this record belongs to
+    pub pubkey: Pubkey, // Member wallet
+    pub status: ParticipantStatus,
+    pub seat: u8, // Payout position, assigned in join order
     pub contribution_round: u16,
-    pub refund_amount: u64,
+    pub partial_amount: u64, // Installments paid toward the next round
+    pub close_vote_expires_at: Option<i64>, // When this member's close vote lapses, if they voted
+    pub pause_vote_epoch: u16, // Matches the group's epoch while this member's pause vote counts
+    pub first_proposal: u32, // Group's proposal count when this member took their seat
+    pub refund_claimed: bool,
+    pub payout_destination: Option<Pubkey>, // Wallet receiving the payout instead of `pubkey`
+    pub payout_shares: Vec<PayoutShare>,    // Parts of the payout forwarded elsewhere
+    pub bumps: u8,                          // PDA bump
+}
+
+impl AjoParticipant {
+    pub const CURRENT_VERSION: u8 = 1;
+    pub const MAX_PAYOUT_SHARES: usize = 4;
+
+    // A fresh record in the waiting room
+    pub fn new(group: Pubkey, pubkey: Pubkey, bumps: u8) -> Self {
+        Self {
+            version: Self::CURRENT_VERSION,
+            group,
+            pubkey,
+            status: ParticipantStatus::Pending,
+            seat: 0,
+            contribution_round: 0,
+            partial_amount: 0,
+            close_vote_expires_at: None,
+            pause_vote_epoch: 0,
+            first_proposal: 0,
+            refund_claimed: false,
+            payout_destination: None,
+            payout_shares: Vec::new(),
+            bumps,
+        }
+    }
+
+    pub const SIZE: usize = 8 +    // discriminator
+                            1 +    // version
+                            32 +   // group
+                            32 +   // pubkey
+                            1 +    // status
+                            1 +    // seat
+                            2 +    // contribution_round
+                            8 +    // partial_amount
+                            1 + 8 + // close_vote_expires_at
+                            2 +    // pause_vote_epoch
+                            4 +    // first_proposal
+                            1 +    // refund_claimed
+                            33 +   // payout_destination
+                            4 + Self::MAX_PAYOUT_SHARES * PayoutShare::SIZE + // payout_shares
+                            1; // bumps
+
+    pub fn is_active(&self) -> bool {
+        self.status == ParticipantStatus::Active
+    }
+
+    // Wallet this member's payout is sent to
+    pub fn payout_wallet(&self) -> Pubkey {
+        self.payout_destination.unwrap_or(self.pubkey)
+    }
+}
+
+// Part of a member's payout sent straight to another account: a token account
+// for SPL groups, a wallet for native SOL groups
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy)]
+pub struct PayoutShare {
+    pub destination: Pubkey,
+    pub bps: u16, // Share of the payout in basis points
+}
+
+impl PayoutShare {
+    pub const SIZE: usize = 32 + 2;
+    pub const TOTAL_BPS: u16 = 10_000;
+}
+
+// Contribution intervals during which a group's schedule stands still. Both
+// fields count contribution intervals and line up with payout boundaries.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy)]
+pub struct ScheduleSkip {
+    pub from_round: u16, // Intervals elapsed, before any skip, when it starts
+    pub rounds: u16,
+}
+
+impl ScheduleSkip {
+    pub const SIZE: usize = 2 + 2;
+}
+
+// Number of seats that have paid up to `round`
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy)]
+pub struct RoundTally {
+    pub round: u16,
+    pub seats: u8,
 }
 
 #[account]
 pub struct AjoGroup {
+    pub version: u8, // Account layout version, see `migrate_ajo_group`
+
     // Basic group information
     pub name: String,              // Unique name for the group
     pub contribution_amount: u64,  // Amount in USDC to contribute each round
-    pub contribution_interval: u8, // Time between rounds when a user should pay (in days)
-    pub payout_interval: u8,       // Time between payouts (in days)
+    pub mint: Pubkey,              // Token the group is denominated in
+    pub contribution_interval: u8, // Time between rounds when a user should pay (in `time_unit`)
+    pub payout_interval: u8,       // Time between payouts (in `time_unit`)
+    pub time_unit: TimeUnit,       // Unit both intervals are expressed in
     pub num_participants: u8,      // Total number of participants needed
+    pub admin: Pubkey,             // Group creator
+
+    // Member aggregates; per-member state lives in `AjoParticipant` accounts
+    pub seats_filled: u8,     // Seats handed out so far, in join order
+    pub active_members: u8,   // Seats currently held by a member
+    pub pending_requests: u8, // Members in the waiting room / on standby
+    pub contribution_tally: Vec<RoundTally>, // Seats per contribution round
 
-    // Participants and round management
-    pub participants: Vec<AjoParticipant>, // List of all participants (ordered by join time)
+    // Round management
     pub start_timestamp: Option<i64>,
     pub payout_round: u16, // state for payouts made, useful in calc current round, index of recipient
 
-    pub close_votes: Vec<Pubkey>, // Track who has voted to close
-    pub waiting_room: Vec<Pubkey>, // Track users who requested to join the group
+    // When each live close vote lapses, one entry per member who voted to close.
+    // Lapsed entries are dropped before votes are counted, so the length is the
+    // number of members currently in favour of closing.
+    pub close_vote_expiries: Vec<i64>,
+    pub refund_base_round: u16, // Rounds already paid out, not refunded once closed
     pub is_closed: bool,
 
+    pub admin_commission: u8, // Organizer cut of each payout in per-mille (1 = 0.1%)
+    pub admin_commission_accrued: u64, // Withheld commission claimable by the admin
+
+    // Emergency pause, voted by members; paused time does not count toward rounds
+    pub pause_vote_epoch: u16,  // Bumped on every flip to discard old votes
+    pub pause_vote_count: u8,   // Votes to flip the current pause state
+    pub paused_at: Option<i64>, // Set while the group is paused
+    pub paused_secs: i64,       // Completed group pauses (minus protocol overlap)
+    pub pause_protocol_snapshot: i64, // Protocol paused time at group pause start
+    pub protocol_pause_offset: i64, // Protocol paused time at group start
+
+    // Voted schedule skips, e.g. for holidays; the schedule stands still while
+    // a skip is in effect and every later round moves back by its length
+    pub schedule_skips: Vec<ScheduleSkip>,
+
+    pub proposal_count: u32, // Proposals created so far, see `Proposal`
+
+    // Idle funds parked in an approved yield adapter, see `yield_adapter`
+    pub yield_adapter: Option<Pubkey>, // Adapter program the group opted into
+    pub yield_deposited: u64,          // Principal currently held by the adapter
+    pub yield_earned: u64,             // Yield realized so far, shared out on refund
+    pub vacant_rounds: u32,            // Rounds paid by vacant seats, which earn no yield
+
     pub vault_bump: u8,
     pub bumps: u8, // PDA bump
 }
 
 impl AjoGroup {
+    // Layout changes made after version 1 shipped are all part of version 2
+    pub const CURRENT_VERSION: u8 = 2;
+    pub const MAX_SCHEDULE_SKIPS: usize = 4;
+
+    // Native SOL groups hold contributions as lamports on the group account itself
+    pub const NATIVE_MINT: Pubkey = anchor_spl::token::spl_token::native_mint::ID;
+
     // Calculate space required for account
     pub fn calculate_size(name: &str, num_participants: u8) -> usize {
-        // Space for fixed fields
-        let fixed_size = 8 +  // account discriminator
-                        (4 + name.len()) +  // name (string)
-                         8 +  // contribution_amount (u64)
-                         1 +  // contribution_interval (u8)
-                         1 +  // payout_interval (u8)
-                         1 +  // num_participants (u8)
-                         4 +  // participants vector length
-                         8 + 1 + // start_timestamp -> FIX if Optional has its bumps (i64)| Yes it does: 1
-                         2 +  // payout_round (u16)
-                         4 + (num_participants as usize * 32) + // close_votes vector + max pubkeys
-                         4 + (num_participants as usize * 32) + // waiting_room vector + max pubkeys
-                         1 +  // is_closed (bool)
-                         1 + // vault_bump (u8)
-                         1; // bumps (u8)
-
-        // Space for participants (with all their data)
-        // Each participant has: pubkey (32) + contribution_round (2) and refund_amount (8)
-        let participant_size = 32 + 2 + 8; // ~42 bytes per participant
-        let participants_size = num_participants as usize * participant_size; // Max 20 participants
-
-        fixed_size + participants_size
+        8 +  // account discriminator
+        1 +  // version (u8)
+        (4 + name.len()) +  // name (string)
+        8 +  // contribution_amount (u64)
+        32 + // mint (Pubkey)
+        1 +  // contribution_interval (u8)
+        1 +  // payout_interval (u8)
+        TimeUnit::SIZE + // time_unit
+        1 +  // num_participants (u8)
+        32 + // admin (Pubkey)
+        1 +  // seats_filled (u8)
+        1 +  // active_members (u8)
+        1 +  // pending_requests (u8)
+        4 + (num_participants as usize * (2 + 1)) + // contribution_tally, one entry per seat at most
+        8 + 1 + // start_timestamp -> FIX if Optional has its bumps (i64)| Yes it does: 1
+        2 +  // payout_round (u16)
+        4 + (num_participants as usize * 8) + // close_vote_expiries, one per seat at most
+        2 +  // refund_base_round (u16)
+        1 +  // is_closed (bool)
+        1 +  // admin_commission (u8)
+        8 +  // admin_commission_accrued (u64)
+        2 +  // pause_vote_epoch (u16)
+        1 +  // pause_vote_count (u8)
+        1 + 8 + // paused_at (Option<i64>)
+        8 +  // paused_secs (i64)
+        8 +  // pause_protocol_snapshot (i64)
+        8 +  // protocol_pause_offset (i64)
+        4 + Self::MAX_SCHEDULE_SKIPS * ScheduleSkip::SIZE + // schedule_skips
+        4 +  // proposal_count (u32)
+        1 + 32 + // yield_adapter (Option<Pubkey>)
+        8 +  // yield_deposited (u64)
+        8 +  // yield_earned (u64)
+        4 +  // vacant_rounds (u32)
+        1 + // vault_bump (u8)
+        1 // bumps (u8)
+    }
+
+    // Contribution rounds in one full cycle, i.e. until every seat has been paid out
+    pub fn total_contribution_rounds(&self) -> u16 {
+        self.num_participants as u16 * (self.payout_interval / self.contribution_interval) as u16
+    }
+
+    pub fn is_native(&self) -> bool {
+        self.mint == Self::NATIVE_MINT
+    }
+
+    // Membership, voting, pause and yield state of a new group, seated with its admin
+    pub fn init_membership(&mut self, admin: Pubkey) {
+        self.admin = admin;
+        self.seats_filled = 1;
+        self.active_members = 1;
+        self.pending_requests = 0;
+        self.contribution_tally = vec![RoundTally { round: 0, seats: 1 }];
+        self.payout_round = 0;
+        self.start_timestamp = None;
+        self.close_vote_expiries = Vec::new();
+        self.refund_base_round = 0;
+        self.is_closed = false;
+        self.admin_commission_accrued = 0;
+        self.pause_vote_epoch = 1;
+        self.pause_vote_count = 0;
+        self.paused_at = None;
+        self.paused_secs = 0;
+        self.pause_protocol_snapshot = 0;
+        self.protocol_pause_offset = 0;
+        self.schedule_skips = Vec::new();
+        self.proposal_count = 0;
+        self.yield_adapter = None;
+        self.yield_deposited = 0;
+        self.yield_earned = 0;
+        self.vacant_rounds = 0;
+    }
+
+    // Count a seat as having paid up to `round`
+    pub fn add_seat_round(&mut self, round: u16) {
+        match self
+            .contribution_tally
+            .iter_mut()
+            .find(|t| t.round == round)
+        {
+            Some(tally) => tally.seats += 1,
+            None => self.contribution_tally.push(RoundTally { round, seats: 1 }),
+        }
+    }
+
+    pub fn remove_seat_round(&mut self, round: u16) {
+        if let Some(pos) = self
+            .contribution_tally
+            .iter()
+            .position(|t| t.round == round)
+        {
+            self.contribution_tally[pos].seats -= 1;
+            if self.contribution_tally[pos].seats == 0 {
+                self.contribution_tally.swap_remove(pos);
+            }
+        }
+    }
+
+    pub fn move_seat_round(&mut self, from: u16, to: u16) {
+        self.remove_seat_round(from);
+        self.add_seat_round(to);
+    }
+
+    // Lowest contribution round across every seat, vacant ones included
+    pub fn min_contribution_round(&self) -> Option<u16> {
+        self.contribution_tally.iter().map(|t| t.round).min()
+    }
+
+    // Amount still refundable to a member once the group is closed.
+    // Contributions made for a vacated seat are forfeited.
+    pub fn refund_amount(&self, participant: &AjoParticipant) -> u64 {
+        if !self.is_closed
+            || self.start_timestamp.is_none()
+            || !participant.is_active()
+            || participant.refund_claimed
+        {
+            return 0;
+        }
+
+        let refundable_rounds = participant
+            .contribution_round
+            .saturating_sub(self.refund_base_round);
+        self.contribution_amount * refundable_rounds as u64 + participant.partial_amount
+    }
+
+    // Share of the realized yield owed to a member of a closed group, pro rata
+    // to the rounds they contributed
+    pub fn yield_share(&self, participant: &AjoParticipant) -> u64 {
+        if !self.is_closed || !participant.is_active() || participant.refund_claimed {
+            return 0;
+        }
+
+        let tallied_rounds: u64 = self
+            .contribution_tally
+            .iter()
+            .map(|t| t.round as u64 * t.seats as u64)
+            .sum();
+        let total_rounds = tallied_rounds - self.vacant_rounds as u64;
+        if total_rounds == 0 {
+            return 0;
+        }
+
+        (self.yield_earned as u128 * participant.contribution_round as u128 / total_rounds as u128)
+            as u64
+    }
+
+    // Account for funds returned by the yield adapter. Principal is paid back
+    // first; anything beyond it, or everything left over once the position is
+    // fully withdrawn, is yield.
+    pub fn record_yield_withdrawal(&mut self, returned: u64, full: bool) -> u64 {
+        let principal = returned.min(self.yield_deposited);
+        let earned = returned - principal;
+
+        self.yield_deposited = if full {
+            0
+        } else {
+            self.yield_deposited - principal
+        };
+        self.yield_earned += earned;
+        earned
     }
 }
 
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq)]
+pub enum FeeMode {
+    OnContribution, // Charged to the contributor on top of each contribution
+    OnPayout,       // Withheld from the pot before it is paid out
+}
+
+// Unit a group's contribution and payout intervals are expressed in
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq)]
+pub enum TimeUnit {
+    Seconds,
+    Hours,
+    Days,
+    Weeks,
+    // Calendar months; rounds fall on `day_of_month`, clamped to the last day
+    // of shorter months
+    Months { day_of_month: u8 },
+}
+
+impl TimeUnit {
+    pub const SIZE: usize = 1 + 1; // variant + day_of_month
+
+    // Length of one unit. Months have no fixed length, so a nominal 30 days is
+    // used for bounds checks while schedules follow the calendar.
+    pub fn seconds(self) -> i64 {
+        match self {
+            TimeUnit::Seconds => 1,
+            TimeUnit::Hours => 60 * 60,
+            TimeUnit::Days => 24 * 60 * 60,
+            TimeUnit::Weeks => 7 * 24 * 60 * 60,
+            TimeUnit::Months { .. } => 30 * 24 * 60 * 60,
+        }
+    }
+
+    // Units shorter than a day are bounded by `GroupLimits::min_interval_secs`
+    pub fn is_sub_day(self) -> bool {
+        matches!(self, TimeUnit::Seconds | TimeUnit::Hours)
+    }
+}
+
+// Bounds enforced on the parameters of newly created groups. Interval bounds are
+// in days and apply to every time unit, converted with `TimeUnit::seconds` (so a
+// month counts as 30 days); sub-day units take their lower bound from
+// `min_interval_secs` instead.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy)]
+pub struct GroupLimits {
+    pub min_participants: u8,
+    pub max_participants: u8,
+    pub min_contribution_interval: u8, // days, for day units and longer
+    pub max_contribution_interval: u8, // days, for every unit
+    pub min_payout_interval: u8,       // days, for day units and longer
+    pub max_payout_interval: u8,       // days, for every unit
+    pub max_name_length: u8,
+    pub min_interval_secs: u32, // Shortest interval for sub-day time units, 0 disables them
+}
+
+impl GroupLimits {
+    pub const SIZE: usize = 7 + 4;
+
+    pub const MAX_PARTICIPANTS: u8 = 200;
+    pub const MAX_NAME_LENGTH: u8 = 50;
+
+    pub const DEFAULT: Self = Self {
+        min_participants: 3,
+        max_participants: 20,
+        min_contribution_interval: 1,
+        max_contribution_interval: 90,
+        min_payout_interval: 7,
+        max_payout_interval: 90,
+        max_name_length: 50,
+        min_interval_secs: 0,
+    };
+
+    pub fn is_valid(&self) -> bool {
+        self.min_participants >= 2
+            && self.min_participants <= self.max_participants
+            && self.max_participants <= Self::MAX_PARTICIPANTS
+            && self.min_contribution_interval >= 1
+            && self.min_contribution_interval <= self.max_contribution_interval
+            && self.min_payout_interval >= 1
+            && self.min_payout_interval <= self.max_payout_interval
+            && self.max_name_length >= 1
+            && self.max_name_length <= Self::MAX_NAME_LENGTH
+    }
+}
+
+// Protocol settings the authority can update after initialization
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy)]
+pub struct ProtocolConfig {
+    // Protocol fee
+    pub fee_percentage: u8, // Fee rate in per-mille (1 = 0.1%)
+    pub fee_mode: FeeMode,  // Whether the fee is taken on contributions or payouts
+    pub treasury: Pubkey,   // Owner of the token accounts receiving fees (one per mint)
+
+    pub max_admin_commission: u8, // Upper bound on the commission a group admin may set, in per-mille
+
+    // Paid to whoever cranks a payout or auto-debit collection on someone
+    // else's behalf, in per-mille of the amount moved
+    pub keeper_tip: u8,
+
+    pub close_vote_expiry_secs: u32, // How long close votes stay valid, 0 keeps them until revoked
+
+    pub limits: GroupLimits,
+}
+
+impl ProtocolConfig {
+    pub const MAX_FEE_PERCENTAGE: u8 = 100; // 10%, in per-mille
+    pub const MAX_ADMIN_COMMISSION: u8 = 50; // 5%, in per-mille
+    pub const MAX_KEEPER_TIP: u8 = 10; // 1%, in per-mille
+
+    pub const SIZE: usize = 1 +    // fee_percentage
+                            1 +    // fee_mode
+                            32 +   // treasury
+                            1 +    // max_admin_commission
+                            1 +    // keeper_tip
+                            4 +    // close_vote_expiry_secs
+                            GroupLimits::SIZE; // limits
+}
+
 #[account]
 pub struct GlobalState {
+    pub version: u8, // Account layout version, see `migrate_global_state`
+
+    pub authority: Pubkey, // Allowed to update the protocol configuration
+    pub pending_authority: Option<Pubkey>, // Nominated authority awaiting acceptance
+
     pub total_groups: u64,  // Total number of groups created
     pub active_groups: u64, // Number of currently active groups
-    pub bumps: u8,          // PDA bump
+
+    pub config: ProtocolConfig,
+    pub allowed_mints: Vec<Pubkey>, // Stablecoins groups may be created for
+    pub yield_adapters: Vec<Pubkey>, // Programs groups may deposit idle funds into
+
+    // Protocol-wide circuit breaker
+    pub paused: bool,
+    pub paused_at: i64,         // When the current protocol pause began
+    pub total_paused_secs: i64, // Completed protocol pauses, used to shift group schedules
+
+    pub bumps: u8, // PDA bump
 }
 
 impl GlobalState {
+    pub const CURRENT_VERSION: u8 = 1;
+    pub const MAX_ALLOWED_MINTS: usize = 10;
+    pub const MAX_YIELD_ADAPTERS: usize = 5;
+
     pub const SIZE: usize = 8 +    // discriminator
+                            1 +    // version
+                            32 +   // authority
+                            1 + 32 + // pending_authority
                             8 +    // total_groups
                             8 +    // active_groups
+                            ProtocolConfig::SIZE + // config
+                            4 + 32 * Self::MAX_ALLOWED_MINTS + // allowed_mints
+                            4 + 32 * Self::MAX_YIELD_ADAPTERS + // yield_adapters
+                            1 +    // paused
+                            8 +    // paused_at
+                            8 +    // total_paused_secs
                             1; // bumps
+
+    // Total time the protocol has spent paused up to `now`
+    pub fn paused_secs(&self, now: i64) -> i64 {
+        if self.paused {
+            self.total_paused_secs + (now - self.paused_at)
+        } else {
+            self.total_paused_secs
+        }
+    }
+}
+
+// Contribution terms the members can vote to change before the group starts
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq)]
+pub struct GroupTerms {
+    pub contribution_amount: u64,
+    pub contribution_interval: u8,
+    pub payout_interval: u8,
+    pub time_unit: TimeUnit,
+}
+
+// What a group proposal does once the members approve it
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq)]
+pub enum ProposalKind {
+    HardshipPayout,                     // Move the proposer to the next payout slot
+    RemoveMember { member: Pubkey },    // Vacate the member's seat
+    ScheduleSkip { payout_rounds: u8 }, // See `ScheduleSkip`
+    SetAdminCommission { admin_commission: u8 }, // Per-mille, within the protocol maximum
+    ChangeAdmin { admin: Pubkey },      // Hand the group to another active member
+    SetYieldAdapter { adapter: Option<Pubkey> }, // Opt into an approved adapter, or out
+    UpdateTerms(GroupTerms),            // Only before the group starts
+    FillSeat { seat: u8, replacement: Pubkey }, // Carried out by `fill_vacant_seat`
+}
+
+impl ProposalKind {
+    pub const SIZE: usize = 1 + 33; // variant + largest parameters
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq)]
+pub enum VoteChoice {
+    Yes,
+    No,
+    Abstain,
+}
+
+// A group decision put to a vote, one account per proposal
+#[account]
+pub struct Proposal {
+    pub group: Pubkey,
+    pub id: u32, // Index among the group's proposals, part of the PDA seeds
+    pub proposer: Pubkey,
+    pub kind: ProposalKind,
+    pub reason: String,
+    pub deadline: i64, // Votes are accepted until then
+    // Members holding a seat when the proposal was created. Only they may vote,
+    // and quorum and thresholds are measured against them even if some leave.
+    pub electorate: u8,
+    pub quorum: u8, // Percent of the electorate that must vote, abstentions included
+    pub threshold: u8, // Percent of yes among yes and no votes that must be exceeded
+    pub yes_votes: u8,
+    pub no_votes: u8,
+    pub abstain_votes: u8,
+    pub executed: bool,
+    pub bumps: u8, // PDA bump
+}
+
+impl Proposal {
+    pub const MAX_REASON_LENGTH: usize = 200;
+    pub const MIN_VOTING_PERIOD_SECS: i64 = 60 * 60;
+    pub const MAX_VOTING_PERIOD_SECS: i64 = 30 * 24 * 60 * 60;
+    // Proposers may ask for more, never less
+    pub const MIN_QUORUM: u8 = 50;
+    pub const MIN_THRESHOLD: u8 = 50;
+
+    pub const SIZE: usize = 8 +    // discriminator
+                            32 +   // group
+                            4 +    // id
+                            32 +   // proposer
+                            ProposalKind::SIZE + // kind
+                            4 + Self::MAX_REASON_LENGTH + // reason
+                            8 +    // deadline
+                            1 +    // electorate
+                            1 +    // quorum
+                            1 +    // threshold
+                            1 +    // yes_votes
+                            1 +    // no_votes
+                            1 +    // abstain_votes
+                            1 +    // executed
+                            1; // bumps
+
+    // Approved once voting has closed with quorum and enough yes votes, or
+    // earlier if the yes votes alone already clear the threshold
+    pub fn is_approved(&self, now: i64) -> bool {
+        let electorate = self.electorate as usize;
+        let yes = self.yes_votes as usize;
+        let decisive = yes + self.no_votes as usize;
+        let votes = decisive + self.abstain_votes as usize;
+
+        let quorum_met = votes * 100 >= self.quorum as usize * electorate;
+        let decided_early = yes * 100 > self.threshold as usize * electorate;
+        let passed = now > self.deadline && yes * 100 > self.threshold as usize * decisive;
+        quorum_met && (decided_early || passed)
+    }
+}
+
+// Marks that `voter` voted on `proposal`; its existence prevents double votes
+#[account]
+pub struct ProposalVote {
+    pub proposal: Pubkey,
+    pub voter: Pubkey,
+    pub choice: VoteChoice,
+    pub bumps: u8, // PDA bump
+}
+
+impl ProposalVote {
+    pub const SIZE: usize = 8 + 32 + 32 + 1 + 1;
+}
+
+// Layouts deployed before accounts carried a version byte (version 0).
+// Only used to read old accounts during migration. A version number is only
+// taken once its layout is deployed; changes to a layout that has not shipped
+// yet go into the current version, so each deployed layout has one migration.
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct AjoParticipantV0 {
+    pub pubkey: Pubkey,
+    pub contribution_round: u16,
+    pub refund_amount: u64,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct AjoGroupV0 {
+    pub name: String,
+    pub contribution_amount: u64,
+    pub contribution_interval: u8,
+    pub payout_interval: u8,
+    pub num_participants: u8,
+    pub participants: Vec<AjoParticipantV0>,
+    pub start_timestamp: Option<i64>,
+    pub payout_round: u16,
+    pub close_votes: Vec<Pubkey>,
+    pub waiting_room: Vec<Pubkey>,
+    pub is_closed: bool,
+    pub vault_bump: u8,
+    pub bumps: u8,
+}
+
+impl AjoGroupV0 {
+    // Fields introduced in version 1 start out at their defaults
+    pub fn into_v1(self, protocol_pause_offset: i64) -> AjoGroupV1 {
+        AjoGroupV1 {
+            version: 1,
+            name: self.name,
+            contribution_amount: self.contribution_amount,
+            contribution_interval: self.contribution_interval,
+            payout_interval: self.payout_interval,
+            num_participants: self.num_participants,
+            participants: self
+                .participants
+                .into_iter()
+                .map(|p| AjoParticipantV1 {
+                    pubkey: p.pubkey,
+                    contribution_round: p.contribution_round,
+                    refund_amount: p.refund_amount,
+                    is_vacant: false,
+                })
+                .collect(),
+            start_timestamp: self.start_timestamp,
+            payout_round: self.payout_round,
+            close_votes: self.close_votes,
+            waiting_room: self.waiting_room,
+            is_closed: self.is_closed,
+            admin_commission: 0,
+            admin_commission_accrued: 0,
+            pause_votes: vec![],
+            paused_at: None,
+            paused_secs: 0,
+            pause_protocol_snapshot: 0,
+            protocol_pause_offset,
+            vault_bump: self.vault_bump,
+            bumps: self.bumps,
+        }
+    }
+}
+
+// Version 1 still kept every member inside the group account
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct AjoParticipantV1 {
+    pub pubkey: Pubkey,
+    pub contribution_round: u16,
+    pub refund_amount: u64,
+    pub is_vacant: bool,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct AjoGroupV1 {
+    pub version: u8,
+    pub name: String,
+    pub contribution_amount: u64,
+    pub contribution_interval: u8,
+    pub payout_interval: u8,
+    pub num_participants: u8,
+    pub participants: Vec<AjoParticipantV1>,
+    pub start_timestamp: Option<i64>,
+    pub payout_round: u16,
+    pub close_votes: Vec<Pubkey>,
+    pub waiting_room: Vec<Pubkey>,
+    pub is_closed: bool,
+    pub admin_commission: u8,
+    pub admin_commission_accrued: u64,
+    pub pause_votes: Vec<Pubkey>,
+    pub paused_at: Option<i64>,
+    pub paused_secs: i64,
+    pub pause_protocol_snapshot: i64,
+    pub protocol_pause_offset: i64,
+    pub vault_bump: u8,
+    pub bumps: u8,
+}
+
+impl AjoGroupV1 {
+    // Read a group stored as version 1 or 0, given the data after the
+    // discriminator. Version 0 groups are lifted to version 1 first, with
+    // `protocol_pause_offset` as their offset. Returns the version read.
+    pub fn decode(data: &[u8], protocol_pause_offset: i64) -> Result<(Self, u8)> {
+        match Self::deserialize(&mut &data[..]) {
+            Ok(v1) if v1.version == 1 => Ok((v1, 1)),
+            _ => {
+                let v0 = AjoGroupV0::deserialize(&mut &data[..])
+                    .map_err(|_| KooPaaError::UnknownAccountLayout)?;
+                Ok((v0.into_v1(protocol_pause_offset), 0))
+            }
+        }
+    }
+
+    // Member state moves out into `AjoParticipant` records, seated members first
+    // and then the waiting room, each still missing its PDA bump. The mint was
+    // never stored, so it is read from the group vault.
+    // Open close votes carry over as if cast now, lapsing at `close_vote_expires_at`
+    pub fn into_current(
+        self,
+        group: Pubkey,
+        mint: Pubkey,
+        close_vote_expires_at: i64,
+    ) -> (AjoGroup, Vec<AjoParticipant>) {
+        let pause_vote_epoch = 1;
+        let mut members = Vec::with_capacity(self.participants.len() + self.waiting_room.len());
+
+        for (seat, p) in self.participants.iter().enumerate() {
+            members.push(AjoParticipant {
+                version: AjoParticipant::CURRENT_VERSION,
+                group,
+                pubkey: p.pubkey,
+                status: if p.is_vacant {
+                    ParticipantStatus::Vacant
+                } else {
+                    ParticipantStatus::Active
+                },
+                seat: seat as u8,
+                contribution_round: p.contribution_round,
+                partial_amount: 0,
+                close_vote_expires_at: (!p.is_vacant && self.close_votes.contains(&p.pubkey))
+                    .then_some(close_vote_expires_at),
+                pause_vote_epoch: if self.pause_votes.contains(&p.pubkey) {
+                    pause_vote_epoch
+                } else {
+                    0
+                },
+                first_proposal: 0,
+                refund_claimed: false,
+                payout_destination: None,
+                payout_shares: Vec::new(),
+                bumps: 0,
+            });
+        }
+
+        for pubkey in &self.waiting_room {
+            members.push(AjoParticipant {
+                version: AjoParticipant::CURRENT_VERSION,
+                group,
+                pubkey: *pubkey,
+                status: ParticipantStatus::Pending,
+                seat: 0,
+                contribution_round: 0,
+                partial_amount: 0,
+                close_vote_expires_at: None,
+                pause_vote_epoch: 0,
+                first_proposal: 0,
+                refund_claimed: false,
+                payout_destination: None,
+                payout_shares: Vec::new(),
+                bumps: 0,
+            });
+        }
+
+        let mut migrated = AjoGroup {
+            version: AjoGroup::CURRENT_VERSION,
+            name: self.name,
+            contribution_amount: self.contribution_amount,
+            mint,
+            contribution_interval: self.contribution_interval,
+            payout_interval: self.payout_interval,
+            time_unit: TimeUnit::Days,
+            num_participants: self.num_participants,
+            admin: self
+                .participants
+                .first()
+                .map(|p| p.pubkey)
+                .unwrap_or_default(),
+            seats_filled: self.participants.len() as u8,
+            active_members: self.participants.iter().filter(|p| !p.is_vacant).count() as u8,
+            pending_requests: self.waiting_room.len() as u8,
+            contribution_tally: vec![],
+            start_timestamp: self.start_timestamp,
+            payout_round: self.payout_round,
+            close_vote_expiries: members
+                .iter()
+                .filter_map(|member| member.close_vote_expires_at)
+                .collect(),
+            refund_base_round: 0,
+            is_closed: self.is_closed,
+            admin_commission: self.admin_commission,
+            admin_commission_accrued: self.admin_commission_accrued,
+            pause_vote_epoch,
+            pause_vote_count: self.pause_votes.len() as u8,
+            paused_at: self.paused_at,
+            paused_secs: self.paused_secs,
+            pause_protocol_snapshot: self.pause_protocol_snapshot,
+            protocol_pause_offset: self.protocol_pause_offset,
+            schedule_skips: Vec::new(),
+            proposal_count: 0,
+            yield_adapter: None,
+            yield_deposited: 0,
+            yield_earned: 0,
+            vacant_rounds: self
+                .participants
+                .iter()
+                .filter(|p| p.is_vacant)
+                .map(|p| p.contribution_round as u32)
+                .sum(),
+            vault_bump: self.vault_bump,
+            bumps: self.bumps,
+        };
+
+        for p in &self.participants {
+            migrated.add_seat_round(p.contribution_round);
+        }
+
+        // Closed groups stored each refund; an emptied refund that was owed
+        // means it has already been claimed
+        if migrated.is_closed {
+            migrated.refund_base_round = migrated.min_contribution_round().unwrap_or(0);
+            for (member, p) in members.iter_mut().zip(&self.participants) {
+                member.refund_claimed = p.refund_amount == 0 && migrated.refund_amount(member) > 0;
+            }
+        }
+
+        (migrated, members)
+    }
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct GlobalStateV0 {
+    pub total_groups: u64,
+    pub active_groups: u64,
+    pub bumps: u8,
+}
+
+impl GlobalStateV0 {
+    // Version 0 had no authority or configuration, so both are supplied by the migration
+    pub fn into_current(self, authority: Pubkey, config: ProtocolConfig) -> GlobalState {
+        GlobalState {
+            version: GlobalState::CURRENT_VERSION,
+            authority,
+            pending_authority: None,
+            total_groups: self.total_groups,
+            active_groups: self.active_groups,
+            config,
+            allowed_mints: vec![],
+            yield_adapters: vec![],
+            paused: false,
+            paused_at: 0,
+            total_paused_secs: 0,
+            bumps: self.bumps,
+        }
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+
+    // A freshly created group, for the unit tests across the crate
+    pub(crate) fn test_group(time_unit: TimeUnit, num_participants: u8) -> AjoGroup {
+        let mut group = AjoGroup {
+            version: AjoGroup::CURRENT_VERSION,
+            name: "Test Group".to_string(),
+            contribution_amount: 100,
+            mint: Pubkey::new_unique(),
+            contribution_interval: 1,
+            payout_interval: 1,
+            time_unit,
+            num_participants,
+            admin: Pubkey::default(),
+            seats_filled: 0,
+            active_members: 0,
+            pending_requests: 0,
+            contribution_tally: vec![],
+            start_timestamp: None,
+            payout_round: 0,
+            close_vote_expiries: vec![],
+            refund_base_round: 0,
+            is_closed: false,
+            admin_commission: 0,
+            admin_commission_accrued: 0,
+            pause_vote_epoch: 0,
+            pause_vote_count: 0,
+            paused_at: None,
+            paused_secs: 0,
+            pause_protocol_snapshot: 0,
+            protocol_pause_offset: 0,
+            schedule_skips: vec![],
+            proposal_count: 0,
+            yield_adapter: None,
+            yield_deposited: 0,
+            yield_earned: 0,
+            vacant_rounds: 0,
+            vault_bump: 0,
+            bumps: 0,
+        };
+        group.init_membership(Pubkey::new_unique());
+        group
+    }
+
+    pub(crate) fn member(seat: u8, contribution_round: u16) -> AjoParticipant {
+        AjoParticipant {
+            status: ParticipantStatus::Active,
+            seat,
+            contribution_round,
+            ..AjoParticipant::new(Pubkey::new_unique(), Pubkey::new_unique(), 0)
+        }
+    }
+
+    pub(crate) fn test_config() -> ProtocolConfig {
+        ProtocolConfig {
+            fee_percentage: 5,
+            fee_mode: FeeMode::OnContribution,
+            treasury: Pubkey::new_unique(),
+            max_admin_commission: 20,
+            keeper_tip: 0,
+            close_vote_expiry_secs: 0,
+            limits: GroupLimits::DEFAULT,
+        }
+    }
+
+    pub(crate) fn test_global_state() -> GlobalState {
+        GlobalState {
+            version: GlobalState::CURRENT_VERSION,
+            authority: Pubkey::new_unique(),
+            pending_authority: None,
+            total_groups: 0,
+            active_groups: 0,
+            config: test_config(),
+            allowed_mints: vec![],
+            yield_adapters: vec![],
+            paused: false,
+            paused_at: 0,
+            total_paused_secs: 0,
+            bumps: 0,
+        }
+    }
+
+    // Three seats that paid 4, 4 and 2 rounds, with 100 of realized yield
+    fn group_with_yield() -> (AjoGroup, Vec<AjoParticipant>) {
+        let mut group = test_group(TimeUnit::Days, 3);
+        let members = vec![member(0, 4), member(1, 4), member(2, 2)];
+        group.active_members = 3;
+        group.contribution_tally = vec![
+            RoundTally { round: 4, seats: 2 },
+            RoundTally { round: 2, seats: 1 },
+        ];
+
+        // Deposit 1000, take 400 back, then withdraw the rest with yield
+        group.yield_deposited = 1_000;
+        assert_eq!(group.record_yield_withdrawal(400, false), 0);
+        assert_eq!(group.yield_deposited, 600);
+        assert_eq!(group.record_yield_withdrawal(700, true), 100);
+        assert_eq!(group.yield_deposited, 0);
+        assert_eq!(group.yield_earned, 100);
+
+        (group, members)
+    }
+
+    #[test]
+    fn yield_is_shared_pro_rata_to_rounds_paid() {
+        let (mut group, members) = group_with_yield();
+        assert_eq!(group.yield_share(&members[0]), 0);
+
+        group.is_closed = true;
+        assert_eq!(group.yield_share(&members[0]), 40);
+        assert_eq!(group.yield_share(&members[1]), 40);
+        assert_eq!(group.yield_share(&members[2]), 20);
+    }
+
+    #[test]
+    fn vacant_seats_earn_no_yield() {
+        let (mut group, mut members) = group_with_yield();
+        crate::utils::vacate_participant(&mut group, &mut members[2]);
+        group.is_closed = true;
+
+        assert_eq!(group.yield_share(&members[0]), 50);
+        assert_eq!(group.yield_share(&members[1]), 50);
+        assert_eq!(group.yield_share(&members[2]), 0);
+    }
+
+    #[test]
+    fn claimed_refunds_earn_no_more_yield() {
+        let (mut group, mut members) = group_with_yield();
+        group.is_closed = true;
+        members[0].refund_claimed = true;
+        assert_eq!(group.yield_share(&members[0]), 0);
+    }
+
+    #[test]
+    fn tally_tracks_the_lowest_round_paid() {
+        let mut group = test_group(TimeUnit::Days, 3);
+        group.add_seat_round(0);
+        group.add_seat_round(0);
+
+        group.move_seat_round(0, 2);
+        group.move_seat_round(0, 1);
+        assert_eq!(group.min_contribution_round(), Some(0));
+
+        group.move_seat_round(0, 1);
+        assert_eq!(group.min_contribution_round(), Some(1));
+        assert_eq!(group.contribution_tally.len(), 2);
+    }
+
+    #[test]
+    fn refunds_cover_rounds_paid_beyond_the_payouts() {
+        let mut group = test_group(TimeUnit::Days, 3);
+        group.start_timestamp = Some(0);
+        let mut paid_ahead = member(1, 3);
+        assert_eq!(group.refund_amount(&paid_ahead), 0);
+
+        group.is_closed = true;
+        group.refund_base_round = 2;
+        assert_eq!(group.refund_amount(&paid_ahead), 100);
+        assert_eq!(group.refund_amount(&member(0, 2)), 0);
+
+        // Installments toward a round that never completed are refunded too
+        let paying_in_installments = AjoParticipant {
+            partial_amount: 40,
+            ..member(0, 2)
+        };
+        assert_eq!(group.refund_amount(&paying_in_installments), 40);
+
+        // Vacated seats forfeit their contributions, and refunds are paid once
+        let vacated = AjoParticipant {
+            status: ParticipantStatus::Vacant,
+            ..member(2, 3)
+        };
+        assert_eq!(group.refund_amount(&vacated), 0);
+        paid_ahead.refund_claimed = true;
+        assert_eq!(group.refund_amount(&paid_ahead), 0);
+    }
+
+    #[test]
+    fn protocol_pauses_add_up() {
+        let mut global_state = test_global_state();
+        global_state.total_paused_secs = 100;
+        assert_eq!(global_state.paused_secs(1_000), 100);
+
+        // The ongoing pause counts up to now
+        global_state.paused = true;
+        global_state.paused_at = 900;
+        assert_eq!(global_state.paused_secs(1_000), 200);
+    }
+
+    // A proposal needing a 60% quorum and more than 50% yes, voting until 100
+    fn proposal(electorate: u8, yes_votes: u8, no_votes: u8, abstain_votes: u8) -> Proposal {
+        Proposal {
+            group: Pubkey::new_unique(),
+            id: 0,
+            proposer: Pubkey::new_unique(),
+            kind: ProposalKind::HardshipPayout,
+            reason: String::new(),
+            deadline: 100,
+            electorate,
+            quorum: 60,
+            threshold: 50,
+            yes_votes,
+            no_votes,
+            abstain_votes,
+            executed: false,
+            bumps: 0,
+        }
+    }
+
+    #[test]
+    fn proposals_pass_after_the_deadline_with_quorum_and_threshold() {
+        // 3 of 5 voted and yes beat no, but only once voting has closed
+        assert!(!proposal(5, 2, 1, 0).is_approved(100));
+        assert!(proposal(5, 2, 1, 0).is_approved(101));
+
+        // Ties and missing quorum fail
+        assert!(!proposal(5, 1, 1, 1).is_approved(101));
+        assert!(!proposal(5, 2, 0, 0).is_approved(101));
+    }
+
+    #[test]
+    fn proposals_pass_early_on_a_majority_of_the_electorate() {
+        assert!(proposal(5, 3, 0, 0).is_approved(0));
+        assert!(!proposal(6, 3, 1, 0).is_approved(0));
+    }
+
+    #[test]
+    fn abstentions_count_toward_quorum_only() {
+        assert!(proposal(5, 1, 0, 2).is_approved(101));
+        assert!(!proposal(5, 1, 1, 2).is_approved(101));
+    }
+
+    #[test]
+    fn electorate_is_fixed_at_creation() {
+        // Members leaving after voting do not lower the bar for the rest
+        let pending = proposal(10, 4, 1, 1);
+        assert!(!pending.is_approved(0));
+        assert!(pending.is_approved(101));
+        assert!(!proposal(10, 4, 1, 0).is_approved(101));
+    }
+
+    fn v0_group(participants: Vec<(Pubkey, u16, u64)>, is_closed: bool) -> AjoGroupV0 {
+        AjoGroupV0 {
+            name: "Legacy Group".to_string(),
+            contribution_amount: 100,
+            contribution_interval: 7,
+            payout_interval: 7,
+            num_participants: 3,
+            participants: participants
+                .into_iter()
+                .map(
+                    |(pubkey, contribution_round, refund_amount)| AjoParticipantV0 {
+                        pubkey,
+                        contribution_round,
+                        refund_amount,
+                    },
+                )
+                .collect(),
+            start_timestamp: Some(1_000),
+            payout_round: 1,
+            close_votes: vec![],
+            waiting_room: vec![],
+            is_closed,
+            vault_bump: 254,
+            bumps: 255,
+        }
+    }
+
+    // Decode an account the way `migrate_ajo_group` does
+    fn migrate_group_data(
+        data: &[u8],
+        group: Pubkey,
+        mint: Pubkey,
+    ) -> (AjoGroup, Vec<AjoParticipant>) {
+        let (legacy, _) = AjoGroupV1::decode(&data[8..], 50).unwrap();
+        legacy.into_current(group, mint, 5_000)
+    }
+
+    fn account_data<T: AnchorSerialize>(account: &T) -> Vec<u8> {
+        let mut data = AjoGroup::DISCRIMINATOR.to_vec();
+        account.serialize(&mut data).unwrap();
+        data
+    }
+
+    #[test]
+    fn v0_group_migrates_to_current_layout() {
+        let (creator, voter, late) = (
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+        );
+        let standby = Pubkey::new_unique();
+        let mut legacy = v0_group(vec![(creator, 2, 0), (voter, 2, 0), (late, 1, 0)], false);
+        legacy.close_votes = vec![voter];
+        legacy.waiting_room = vec![standby];
+
+        let (group_key, mint) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let (group, members) = migrate_group_data(&account_data(&legacy), group_key, mint);
+
+        assert_eq!(group.version, AjoGroup::CURRENT_VERSION);
+        assert_eq!(group.mint, mint);
+        assert!(group.time_unit == TimeUnit::Days);
+        assert_eq!(group.admin, creator);
+        assert_eq!(group.seats_filled, 3);
+        assert_eq!(group.active_members, 3);
+        assert_eq!(group.pending_requests, 1);
+        assert_eq!(group.close_vote_expiries, vec![5_000]);
+        assert_eq!(group.protocol_pause_offset, 50);
+        assert_eq!(group.min_contribution_round(), Some(1));
+        assert_eq!(group.start_timestamp, Some(1_000));
+        assert_eq!(group.payout_round, 1);
+        assert_eq!((group.vault_bump, group.bumps), (254, 255));
+
+        assert_eq!(members.len(), 4);
+        for (seat, member) in members[..3].iter().enumerate() {
+            assert_eq!(member.group, group_key);
+            assert_eq!(member.seat as usize, seat);
+            assert!(member.is_active());
+        }
+        assert_eq!(members[1].close_vote_expires_at, Some(5_000));
+        assert_eq!(members[0].close_vote_expires_at, None);
+        assert_eq!(members[2].contribution_round, 1);
+        assert_eq!(members[3].pubkey, standby);
+        assert!(members[3].status == ParticipantStatus::Pending);
+
+        // The migrated account fits the space it is resized to
+        let mut migrated = Vec::new();
+        group.serialize(&mut migrated).unwrap();
+        assert!(
+            8 + migrated.len() <= AjoGroup::calculate_size(&group.name, group.num_participants)
+        );
+    }
+
+    #[test]
+    fn closed_v0_group_keeps_claimed_refunds() {
+        let members = vec![
+            (Pubkey::new_unique(), 3, 0),   // Already claimed
+            (Pubkey::new_unique(), 3, 100), // Still owed
+            (Pubkey::new_unique(), 2, 0),   // Nothing owed
+        ];
+        let legacy = v0_group(members, true);
+
+        let (group, members) = migrate_group_data(
+            &account_data(&legacy),
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+        );
+
+        assert!(group.is_closed);
+        assert_eq!(group.refund_base_round, 2);
+        assert!(members[0].refund_claimed);
+        assert!(!members[1].refund_claimed);
+        assert_eq!(group.refund_amount(&members[1]), 100);
+        assert!(!members[2].refund_claimed);
+        assert_eq!(group.refund_amount(&members[2]), 0);
+    }
+
+    #[test]
+    fn v0_global_state_migrates_to_current_layout() {
+        let legacy = GlobalStateV0 {
+            total_groups: 12,
+            active_groups: 5,
+            bumps: 253,
+        };
+        let mut data = GlobalState::DISCRIMINATOR.to_vec();
+        legacy.serialize(&mut data).unwrap();
+
+        // Too short for the current layout, so it is read as version 0
+        assert!(GlobalState::try_deserialize(&mut &data[..]).is_err());
+
+        let (authority, config) = (Pubkey::new_unique(), test_config());
+        let state = GlobalStateV0::deserialize(&mut &data[8..])
+            .unwrap()
+            .into_current(authority, config);
+
+        assert_eq!(state.version, GlobalState::CURRENT_VERSION);
+        assert_eq!(state.authority, authority);
+        assert_eq!(state.pending_authority, None);
+        assert_eq!((state.total_groups, state.active_groups), (12, 5));
+        assert_eq!(state.config.treasury, config.treasury);
+        assert!(state.allowed_mints.is_empty() && state.yield_adapters.is_empty());
+        assert!(!state.paused);
+        assert_eq!(state.bumps, 253);
+
+        let mut migrated = Vec::new();
+        state.serialize(&mut migrated).unwrap();
+        assert!(8 + migrated.len() <= GlobalState::SIZE);
+    }
+
+    #[test]
+    fn unknown_group_layout_is_rejected() {
+        assert!(AjoGroupV1::decode(&[1, 2, 3], 0).is_err());
+    }
 }
@@ -1,14 +1,1411 @@
+use crate::errors::KooPaaError;
+use crate::events::*;
 use crate::state::*;
+use crate::yield_adapter::*;
 use anchor_lang::prelude::*;
+use anchor_lang::system_program;
+use anchor_spl::token_2022::spl_token_2022::{
+    self,
+    extension::{
+        transfer_fee::TransferFeeConfig, BaseStateWithExtensions, ExtensionType,
+        StateWithExtensions,
+    },
+};
+use anchor_spl::token_interface::{transfer_checked, TokenAccount, TransferChecked};
 
 // Helper function to find the PDA for an Ajo group
 pub fn find_group_pda(name: &str, program_id: &Pubkey) -> (Pubkey, u8) {
     Pubkey::find_program_address(&[b"ajo-group", name.as_bytes()], program_id)
 }
 
-// Convert days to seconds
-pub fn days_to_seconds(days: u16) -> i64 {
-    (days as i64) * 24 * 60 * 60
+// Convert an interval in the group's time unit to seconds
+pub fn interval_to_seconds(interval: u16, time_unit: TimeUnit) -> i64 {
+    (interval as i64) * time_unit.seconds()
+}
+
+// Whole `interval`s the schedule has moved through `time_since_start` after the
+// group started. Monthly groups count the calendar dates they fall on instead.
+pub fn intervals_elapsed(group: &AjoGroup, interval: u8, time_since_start: i64) -> u16 {
+    let TimeUnit::Months { day_of_month } = group.time_unit else {
+        return (time_since_start / interval_to_seconds(interval as u16, group.time_unit)) as u16;
+    };
+
+    // Time spent paused pushes the calendar dates back like any other schedule
+    let start = group.start_timestamp.unwrap_or_default();
+    let now = start + time_since_start;
+
+    // First date strictly after the start, and the last one already reached
+    let start_month = month_index(start);
+    let first_month = if month_date(start_month, day_of_month) > start {
+        start_month
+    } else {
+        start_month + 1
+    };
+    let now_month = month_index(now);
+    let last_month = if month_date(now_month, day_of_month) <= now {
+        now_month
+    } else {
+        now_month - 1
+    };
+
+    let dates_reached = (last_month - first_month + 1).max(0);
+    (dates_reached / interval as i64) as u16
+}
+
+const SECONDS_PER_DAY: i64 = 24 * 60 * 60;
+
+// Months since year 0 of the UTC date `timestamp` falls on
+fn month_index(timestamp: i64) -> i64 {
+    let (year, month, _) = civil_from_days(timestamp.div_euclid(SECONDS_PER_DAY));
+    year * 12 + month as i64 - 1
+}
+
+// Midnight UTC of `day_of_month` in the month `month_index`, clamped to the
+// month's last day
+fn month_date(month_index: i64, day_of_month: u8) -> i64 {
+    let year = month_index.div_euclid(12);
+    let month = month_index.rem_euclid(12) as u32 + 1;
+    let day = (day_of_month as u32).min(days_in_month(year, month));
+    days_from_civil(year, month, day) * SECONDS_PER_DAY
+}
+
+fn days_in_month(year: i64, month: u32) -> u32 {
+    match month {
+        2 if year % 4 == 0 && (year % 100 != 0 || year % 400 == 0) => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    }
+}
+
+// Days since 1970-01-01 for a proleptic Gregorian date
+fn days_from_civil(year: i64, month: u32, day: u32) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year.rem_euclid(400);
+    let month_from_march = (month as i64 + 9) % 12;
+    let day_of_year = (153 * month_from_march + 2) / 5 + day as i64 - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era * 146_097 + day_of_era - 719_468
+}
+
+// Proleptic Gregorian (year, month, day) for days since 1970-01-01
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let days = days + 719_468;
+    let era = days.div_euclid(146_097);
+    let day_of_era = days.rem_euclid(146_097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_from_march = (5 * day_of_year + 2) / 153;
+    let day = (day_of_year - (153 * month_from_march + 2) / 5 + 1) as u32;
+    let month = if month_from_march < 10 {
+        month_from_march + 3
+    } else {
+        month_from_march - 9
+    } as u32;
+    let year = year_of_era + era * 400 + if month <= 2 { 1 } else { 0 };
+    (year, month, day)
+}
+
+// Time since the group started that counts toward its schedule, i.e. excluding
+// any time the group or the whole protocol was paused
+pub fn active_time_since_start(
+    group: &AjoGroup,
+    global_state: &GlobalState,
+    now: i64,
+) -> Option<i64> {
+    let start_timestamp = group.start_timestamp?;
+    let protocol_paused = global_state.paused_secs(now);
+
+    // Protocol pauses overlapping the ongoing group pause are already covered
+    // by `protocol_paused`, so only the remainder is attributed to the group
+    let group_paused = match group.paused_at {
+        Some(paused_at) => {
+            group.paused_secs + (now - paused_at)
+                - (protocol_paused - group.pause_protocol_snapshot)
+        }
+        None => group.paused_secs,
+    };
+
+    let elapsed =
+        now - start_timestamp - group_paused - (protocol_paused - group.protocol_pause_offset);
+    Some(elapsed.max(0))
+}
+
+// Contribution round the group is currently in, counted from the start timestamp
+pub fn current_contribution_round(
+    group: &AjoGroup,
+    global_state: &GlobalState,
+    now: i64,
+) -> Option<u16> {
+    let time_since_start = active_time_since_start(group, global_state, now)?;
+    Some(scheduled_rounds(group, time_since_start))
+}
+
+// Current contribution round of a group that is accepting contributions
+pub fn open_contribution_round(
+    group: &AjoGroup,
+    global_state: &GlobalState,
+    now: i64,
+) -> Result<u16> {
+    require!(!global_state.paused, KooPaaError::ProtocolPaused);
+    require!(group.paused_at.is_none(), KooPaaError::GroupPaused);
+    current_contribution_round(group, global_state, now)
+        .ok_or_else(|| KooPaaError::GroupNotStarted.into())
+}
+
+// Contribution intervals the schedule has advanced by, leaving out the
+// intervals covered by voted skips
+pub fn scheduled_rounds(group: &AjoGroup, time_since_start: i64) -> u16 {
+    let elapsed = intervals_elapsed(group, group.contribution_interval, time_since_start);
+    let skipped: u16 = group
+        .schedule_skips
+        .iter()
+        .map(|skip| elapsed.saturating_sub(skip.from_round).min(skip.rounds))
+        .sum();
+    elapsed - skipped
+}
+
+// Check a skip of `payout_rounds` covers at least one payout interval and no
+// more than the payouts the cycle still has to make
+pub fn validate_skip_rounds(group: &AjoGroup, payout_rounds: u8) -> Result<()> {
+    let rounds_left = (group.num_participants as u16).saturating_sub(group.payout_round);
+    require!(
+        payout_rounds > 0 && payout_rounds as u16 <= rounds_left,
+        KooPaaError::InvalidSkipRounds
+    );
+    Ok(())
+}
+
+// Schedule a skip of `payout_rounds` payout intervals, starting at the next
+// payout boundary or right after a skip that is still running
+pub fn schedule_skip(
+    group: &mut AjoGroup,
+    time_since_start: i64,
+    payout_rounds: u8,
+) -> Result<ScheduleSkip> {
+    require!(
+        group.schedule_skips.len() < AjoGroup::MAX_SCHEDULE_SKIPS,
+        KooPaaError::ScheduleSkipsFull
+    );
+    validate_skip_rounds(group, payout_rounds)?;
+
+    let per_payout = (group.payout_interval / group.contribution_interval) as u16;
+    let elapsed = intervals_elapsed(group, group.contribution_interval, time_since_start);
+    let current = scheduled_rounds(group, time_since_start);
+
+    let next_boundary = (current / per_payout + 1)
+        .checked_mul(per_payout)
+        .and_then(|boundary| (elapsed - current).checked_add(boundary))
+        .ok_or(KooPaaError::MathOverflow)?;
+    let from_round = group
+        .schedule_skips
+        .iter()
+        .map(|skip| skip.from_round.saturating_add(skip.rounds))
+        .filter(|end| *end > elapsed)
+        .max()
+        .unwrap_or(next_boundary);
+
+    let rounds = (payout_rounds as u16)
+        .checked_mul(per_payout)
+        .ok_or(KooPaaError::MathOverflow)?;
+    from_round
+        .checked_add(rounds)
+        .ok_or(KooPaaError::MathOverflow)?;
+
+    let skip = ScheduleSkip { from_round, rounds };
+    group.schedule_skips.push(skip);
+    Ok(skip)
+}
+
+// Validate everything a new group is created with against the protocol config
+#[allow(clippy::too_many_arguments)]
+pub fn validate_new_group(
+    config: &ProtocolConfig,
+    name: &str,
+    contribution_amount: u64,
+    contribution_interval: u8,
+    payout_interval: u8,
+    num_participants: u8,
+    admin_commission: u8,
+    time_unit: TimeUnit,
+) -> Result<()> {
+    validate_group_params(
+        &config.limits,
+        contribution_amount,
+        contribution_interval,
+        payout_interval,
+        num_participants,
+        time_unit,
+    )?;
+    require!(
+        name.len() <= config.limits.max_name_length as usize,
+        KooPaaError::NameTooLong
+    );
+    require!(
+        admin_commission <= config.max_admin_commission,
+        KooPaaError::InvalidAdminCommission
+    );
+    Ok(())
+}
+
+// Round a member will have paid up to after catching up to `current_round` and
+// prepaying `prepay_rounds` more, and the amount that costs
+pub fn contribution_due(
+    group: &AjoGroup,
+    participant: &AjoParticipant,
+    current_round: u16,
+    prepay_rounds: u16,
+) -> Result<(u16, u64)> {
+    let last_paid_round = participant.contribution_round;
+    let target_round = current_round
+        .checked_add(prepay_rounds)
+        .ok_or(KooPaaError::MathOverflow)?;
+
+    // Members can pay ahead up to the end of the cycle, never past it
+    require!(
+        prepay_rounds == 0 || target_round <= group.total_contribution_rounds(),
+        KooPaaError::PrepayBeyondLastRound
+    );
+    require!(
+        last_paid_round < target_round,
+        KooPaaError::AlreadyContributed
+    );
+
+    // Installments already paid toward the next round count toward the amount
+    let rounds_paid = target_round - last_paid_round;
+    let amount = group.contribution_amount * rounds_paid as u64 - participant.partial_amount;
+    Ok((target_round, amount))
+}
+
+// Credit an installment toward the member's next unpaid round. The round only
+// counts as paid once the installments add up to the full contribution amount.
+pub fn apply_installment(
+    group: &mut AjoGroup,
+    participant: &mut AjoParticipant,
+    current_round: u16,
+    amount: u64,
+) -> Result<()> {
+    let outstanding = group.contribution_amount - participant.partial_amount;
+    require!(
+        amount > 0 && amount <= outstanding,
+        KooPaaError::InvalidInstallmentAmount
+    );
+
+    let next_round = participant.contribution_round + 1;
+    require!(
+        next_round <= current_round.max(group.total_contribution_rounds()),
+        KooPaaError::PrepayBeyondLastRound
+    );
+
+    if amount == outstanding {
+        record_contribution(group, participant, next_round);
+    } else {
+        participant.partial_amount += amount;
+    }
+    Ok(())
+}
+
+// Mark a member as paid up to `paid_until_round`
+pub fn record_contribution(
+    group: &mut AjoGroup,
+    participant: &mut AjoParticipant,
+    paid_until_round: u16,
+) {
+    group.move_seat_round(participant.contribution_round, paid_until_round);
+    participant.contribution_round = paid_until_round;
+    participant.partial_amount = 0;
+}
+
+// Protocol fee charged on top of a contribution, when fees are taken on contributions
+pub fn contribution_fee(config: &ProtocolConfig, amount: u64) -> u64 {
+    if config.fee_mode == FeeMode::OnContribution {
+        calculate_fee(amount, config.fee_percentage)
+    } else {
+        0
+    }
+}
+
+// How a transfer moves money: token transfers through the group vault, or
+// lamports for native SOL groups, held on the group account itself
+pub enum TransferProgram<'info> {
+    Token {
+        mint: AccountInfo<'info>,
+        decimals: u8,
+        token_program: AccountInfo<'info>,
+    },
+    System(AccountInfo<'info>),
+}
+
+// Accounts a contribution is paid with. `vault` is the group account for
+// native SOL groups.
+pub struct ContributionTransfer<'info> {
+    pub from: AccountInfo<'info>,
+    pub authority: AccountInfo<'info>,
+    pub vault: AccountInfo<'info>,
+    pub treasury: Option<AccountInfo<'info>>,
+    pub program: TransferProgram<'info>,
+}
+
+// Send funds from the paying account so that `to` is credited with `amount`.
+// Transfer-fee mints withhold part of each transfer, so it is grossed up.
+pub fn transfer_from_payer<'info>(
+    accounts: &ContributionTransfer<'info>,
+    to: &AccountInfo<'info>,
+    amount: u64,
+    signer_seeds: &[&[&[u8]]],
+) -> Result<()> {
+    match &accounts.program {
+        TransferProgram::Token {
+            mint,
+            decimals,
+            token_program,
+        } => {
+            let transfer_accounts = TransferChecked {
+                from: accounts.from.clone(),
+                mint: mint.clone(),
+                to: to.clone(),
+                authority: accounts.authority.clone(),
+            };
+
+            transfer_checked(
+                CpiContext::new_with_signer(token_program.clone(), transfer_accounts, signer_seeds),
+                amount_with_transfer_fee(mint, amount)?,
+                *decimals,
+            )
+        }
+        TransferProgram::System(system_program) => {
+            deposit_lamports(&accounts.from, to, system_program, amount)
+        }
+    }
+}
+
+// Pay a contribution into the vault, and the contribution fee on top into the
+// treasury so the vault receives exactly what the payout math expects.
+// Returns the fee charged.
+pub fn pay_contribution(
+    accounts: &ContributionTransfer,
+    config: &ProtocolConfig,
+    amount: u64,
+    signer_seeds: &[&[&[u8]]],
+) -> Result<u64> {
+    transfer_from_payer(accounts, &accounts.vault, amount, signer_seeds)?;
+
+    let fee_amount = contribution_fee(config, amount);
+    if fee_amount > 0 {
+        let treasury = accounts
+            .treasury
+            .as_ref()
+            .ok_or(KooPaaError::MissingTreasuryAccount)?;
+        transfer_from_payer(accounts, treasury, fee_amount, signer_seeds)?;
+    }
+
+    Ok(fee_amount)
+}
+
+// Where a group pays out from: its token vault, signed for by the group PDA,
+// or the lamports on a native SOL group's own account
+pub enum GroupFunds<'a, 'info> {
+    Token {
+        group: AccountInfo<'info>,
+        vault: AccountInfo<'info>,
+        mint: AccountInfo<'info>,
+        decimals: u8,
+        token_program: AccountInfo<'info>,
+        signer_seeds: &'a [&'a [u8]],
+    },
+    Lamports(AccountInfo<'info>),
+}
+
+impl<'info> GroupFunds<'_, 'info> {
+    pub fn pay_out(&self, to: &AccountInfo<'info>, amount: u64) -> Result<()> {
+        match self {
+            GroupFunds::Token {
+                group,
+                vault,
+                mint,
+                decimals,
+                token_program,
+                signer_seeds,
+            } => {
+                let transfer_accounts = TransferChecked {
+                    from: vault.clone(),
+                    mint: mint.clone(),
+                    to: to.clone(),
+                    authority: group.clone(),
+                };
+
+                transfer_checked(
+                    CpiContext::new_with_signer(
+                        token_program.clone(),
+                        transfer_accounts,
+                        &[signer_seeds],
+                    ),
+                    amount,
+                    *decimals,
+                )
+            }
+            GroupFunds::Lamports(group) => withdraw_lamports(group, to, amount),
+        }
+    }
+}
+
+// Set up a new group around its creator, who takes the first seat. The
+// caller sets the PDA bumps.
+#[allow(clippy::too_many_arguments)]
+pub fn init_group<'info>(
+    group: &mut Account<'info, AjoGroup>,
+    creator_participant: &mut Account<'info, AjoParticipant>,
+    participant_bump: u8,
+    global_state: &mut GlobalState,
+    creator: Pubkey,
+    name: String,
+    terms: GroupTerms,
+    num_participants: u8,
+    admin_commission: u8,
+    mint: Pubkey,
+    now: i64,
+) -> Result<()> {
+    validate_new_group(
+        &global_state.config,
+        &name,
+        terms.contribution_amount,
+        terms.contribution_interval,
+        terms.payout_interval,
+        num_participants,
+        admin_commission,
+        terms.time_unit,
+    )?;
+
+    let payout_interval = round_payout_interval(terms.contribution_interval, terms.payout_interval);
+
+    group.version = AjoGroup::CURRENT_VERSION;
+    group.name = name.clone();
+    group.contribution_amount = terms.contribution_amount;
+    group.mint = mint;
+    group.contribution_interval = terms.contribution_interval;
+    group.payout_interval = payout_interval;
+    group.time_unit = terms.time_unit;
+    group.num_participants = num_participants;
+    group.admin_commission = admin_commission;
+    group.init_membership(creator);
+
+    creator_participant.set_inner(AjoParticipant {
+        status: ParticipantStatus::Active,
+        ..AjoParticipant::new(group.key(), creator, participant_bump)
+    });
+
+    global_state.total_groups += 1;
+
+    emit!(AjoGroupCreatedEvent {
+        group_name: name.clone(),
+        contribution_amount: terms.contribution_amount,
+        mint,
+        num_participants,
+        contribution_interval: terms.contribution_interval,
+        payout_interval,
+        time_unit: terms.time_unit,
+        admin: creator,
+        admin_commission,
+    });
+
+    emit!(ParticipantJoinedEvent {
+        group_name: name,
+        participant: creator,
+        join_timestamp: now,
+        admin_invited: false,
+    });
+
+    Ok(())
+}
+
+// Pay what `participant` owes up to the open round, plus `prepay_rounds`
+// ahead. `transfer.authority` is the wallet paying, not always the member.
+pub fn settle_contribution(
+    group: &mut AjoGroup,
+    global_state: &GlobalState,
+    participant: &mut AjoParticipant,
+    transfer: &ContributionTransfer,
+    prepay_rounds: u16,
+    now: i64,
+) -> Result<()> {
+    let current_round = open_contribution_round(group, global_state, now)?;
+    let (paid_until_round, transfer_amount) =
+        contribution_due(group, participant, current_round, prepay_rounds)?;
+
+    let fee_amount = pay_contribution(transfer, &global_state.config, transfer_amount, &[])?;
+
+    record_contribution(group, participant, paid_until_round);
+
+    emit!(ContributionMadeEvent {
+        group_name: group.name.clone(),
+        contributor: participant.pubkey,
+        payer: transfer.authority.key(),
+        contribution_amount: transfer_amount,
+        fee_amount,
+        current_round,
+        paid_until_round,
+        partial_amount: 0,
+    });
+
+    Ok(())
+}
+
+// Pay an installment toward the member's next unpaid round
+pub fn settle_installment(
+    group: &mut AjoGroup,
+    global_state: &GlobalState,
+    participant: &mut AjoParticipant,
+    transfer: &ContributionTransfer,
+    amount: u64,
+    now: i64,
+) -> Result<()> {
+    let current_round = open_contribution_round(group, global_state, now)?;
+    apply_installment(group, participant, current_round, amount)?;
+
+    let fee_amount = pay_contribution(transfer, &global_state.config, amount, &[])?;
+
+    emit!(ContributionMadeEvent {
+        group_name: group.name.clone(),
+        contributor: participant.pubkey,
+        payer: transfer.authority.key(),
+        contribution_amount: amount,
+        fee_amount,
+        current_round,
+        paid_until_round: participant.contribution_round,
+        partial_amount: participant.partial_amount,
+    });
+
+    Ok(())
+}
+
+// Pay out a round split by `payout_split`: the protocol fee, the keeper's tip,
+// each payout share and the rest to the recipient. The organizer's commission
+// stays with the group until the admin claims it.
+#[allow(clippy::too_many_arguments)]
+pub fn distribute_payout<'info>(
+    group: &mut AjoGroup,
+    funds: &GroupFunds<'_, 'info>,
+    split: &PayoutSplit,
+    recipient: Pubkey,
+    recipient_account: &AccountInfo<'info>,
+    share_transfers: Vec<PayoutShareTransfer>,
+    share_accounts: &[AccountInfo<'info>],
+    treasury: Option<AccountInfo<'info>>,
+    keeper: Option<AccountInfo<'info>>,
+    caller: Pubkey,
+) -> Result<()> {
+    if split.fee_amount > 0 {
+        let treasury = treasury.ok_or(KooPaaError::MissingTreasuryAccount)?;
+        funds.pay_out(&treasury, split.fee_amount)?;
+    }
+
+    if let Some(keeper) = keeper.filter(|_| split.keeper_tip > 0) {
+        funds.pay_out(&keeper, split.keeper_tip)?;
+
+        emit!(KeeperTipPaidEvent {
+            group_name: group.name.clone(),
+            keeper: caller,
+            amount: split.keeper_tip,
+        });
+    }
+
+    group.admin_commission_accrued += split.commission_amount;
+
+    let mut recipient_amount = split.payout_amount;
+    for (share, account) in share_transfers.iter().zip(share_accounts) {
+        funds.pay_out(account, share.amount)?;
+        recipient_amount -= share.amount;
+    }
+    funds.pay_out(recipient_account, recipient_amount)?;
+
+    group.payout_round += 1;
+
+    emit!(PayoutMadeEvent {
+        group_name: group.name.clone(),
+        recipient,
+        payout_amount: split.payout_amount,
+        fee_amount: split.fee_amount,
+        commission_amount: split.commission_amount,
+        payout_round: group.payout_round,
+        shares: share_transfers,
+    });
+
+    Ok(())
+}
+
+// Refunds stay available while the group itself is paused so members can
+// always recover their funds from a closed group
+pub fn check_refunds_open(group: &AjoGroup, global_state: &GlobalState) -> Result<()> {
+    require!(!global_state.paused, KooPaaError::ProtocolPaused);
+    require!(group.is_closed, KooPaaError::GroupNotClosed);
+    Ok(())
+}
+
+// Pay a member of a closed group their refund and share of any yield
+pub fn pay_refund<'info>(
+    group: &AjoGroup,
+    participant: &mut AjoParticipant,
+    funds: &GroupFunds<'_, 'info>,
+    to: &AccountInfo<'info>,
+) -> Result<()> {
+    let refund_amount = group.refund_amount(participant);
+    let yield_amount = group.yield_share(participant);
+    require!(
+        refund_amount + yield_amount > 0,
+        KooPaaError::NoRefundToClaim
+    );
+
+    funds.pay_out(to, refund_amount + yield_amount)?;
+
+    participant.refund_claimed = true;
+
+    emit!(RefundClaimedEvent {
+        group_name: group.name.clone(),
+        participant: participant.pubkey,
+        amount: refund_amount,
+        yield_amount,
+    });
+
+    Ok(())
+}
+
+// Pay the admin the commission accrued on payouts so far
+pub fn pay_admin_commission<'info>(
+    group: &mut AjoGroup,
+    admin: Pubkey,
+    funds: &GroupFunds<'_, 'info>,
+    to: &AccountInfo<'info>,
+) -> Result<()> {
+    require!(group.admin == admin, KooPaaError::OnlyAdminCanUpdate);
+
+    let amount = group.admin_commission_accrued;
+    require!(amount > 0, KooPaaError::NoCommissionToClaim);
+
+    funds.pay_out(to, amount)?;
+
+    group.admin_commission_accrued = 0;
+
+    emit!(AdminCommissionClaimedEvent {
+        group_name: group.name.clone(),
+        admin,
+        amount,
+    });
+
+    Ok(())
+}
+
+// Hand a vacant seat to `transfer.authority`, who pays the seat's arrears
+#[allow(clippy::too_many_arguments)]
+pub fn fill_seat<'info>(
+    group: &mut Account<'info, AjoGroup>,
+    global_state: &GlobalState,
+    caller: Pubkey,
+    proposal: Option<&mut Account<'info, Proposal>>,
+    vacated: &mut AjoParticipant,
+    replacement_participant: &mut AjoParticipant,
+    transfer: &ContributionTransfer<'info>,
+    now: i64,
+) -> Result<()> {
+    require!(!global_state.paused, KooPaaError::ProtocolPaused);
+    require!(group.paused_at.is_none(), KooPaaError::GroupPaused);
+    require!(!group.is_closed, KooPaaError::GroupAlreadyClosed);
+    let current_round =
+        current_contribution_round(group, global_state, now).ok_or(KooPaaError::GroupNotStarted)?;
+
+    let group_key = group.key();
+    let replacement = transfer.authority.key();
+    let proposal_key = proposal.as_ref().map(|proposal| proposal.key());
+    let by_proposal = authorize_seat_fill(
+        group,
+        group_key,
+        caller,
+        proposal.map(|proposal| &mut **proposal),
+        vacated.seat,
+        replacement,
+        now,
+    )?;
+
+    // The replacement settles every round the seat has missed so the
+    // group's contribution and payout schedule carries on unchanged
+    let arrears = hand_over_seat(group, vacated, replacement_participant, current_round)?;
+
+    // Arrears are contributions like any other and carry the same fee
+    let fee_amount = if arrears > 0 {
+        pay_contribution(transfer, &global_state.config, arrears, &[])?
+    } else {
+        0
+    };
+
+    emit!(SeatFilledEvent {
+        group_name: group.name.clone(),
+        previous_participant: vacated.pubkey,
+        replacement,
+        seat_index: vacated.seat,
+        arrears_paid: arrears,
+        fee_amount,
+    });
+
+    if let Some(proposal) = proposal_key.filter(|_| by_proposal) {
+        emit!(ProposalExecutedEvent {
+            group_name: group.name.clone(),
+            proposal,
+            kind: ProposalKind::FillSeat {
+                seat: vacated.seat,
+                replacement,
+            },
+        });
+    }
+
+    Ok(())
+}
+
+// How the pot of a payout round is split
+pub struct PayoutSplit {
+    pub fee_amount: u64,        // Protocol fee, when charged on payouts
+    pub commission_amount: u64, // Organizer commission, kept in the vault
+    pub keeper_tip: u64,        // Paid to the caller for cranking the payout
+    pub payout_amount: u64,     // What the recipient receives
+}
+
+// Check the current payout round is due and owed to `recipient`, and split its
+// pot. The keeper tip is only withheld when `tip_keeper` is set.
+pub fn payout_split(
+    group: &AjoGroup,
+    global_state: &GlobalState,
+    recipient: &AjoParticipant,
+    now: i64,
+    tip_keeper: bool,
+) -> Result<PayoutSplit> {
+    let time_since_start =
+        active_time_since_start(group, global_state, now).ok_or(KooPaaError::GroupNotStarted)?;
+
+    let required_contributions_per_payout = group.payout_interval / group.contribution_interval;
+    let min_required_contribution_rounds =
+        (group.payout_round + 1) * required_contributions_per_payout as u16;
+
+    let all_contributed = group
+        .min_contribution_round()
+        .is_some_and(|round| round >= min_required_contribution_rounds);
+    require!(all_contributed, KooPaaError::NotAllContributed);
+
+    let expected_payout_round =
+        scheduled_rounds(group, time_since_start) / required_contributions_per_payout as u16;
+    require!(
+        group.payout_round < expected_payout_round,
+        KooPaaError::PayoutNotYetDue
+    );
+
+    let recipient_seat = (group.payout_round % group.num_participants as u16) as u8;
+    require!(
+        recipient.seat == recipient_seat,
+        KooPaaError::NotCurrentRecipient
+    );
+    require!(recipient.is_active(), KooPaaError::RecipientSeatVacant);
+
+    let gross_payout = group.contribution_amount
+        * (group.num_participants as u64)
+        * (required_contributions_per_payout as u64);
+
+    let fee_amount = if global_state.config.fee_mode == FeeMode::OnPayout {
+        calculate_fee(gross_payout, global_state.config.fee_percentage)
+    } else {
+        0
+    };
+    let commission_amount = calculate_fee(gross_payout, group.admin_commission);
+    let keeper_tip = if tip_keeper {
+        calculate_fee(gross_payout, global_state.config.keeper_tip)
+    } else {
+        0
+    };
+
+    Ok(PayoutSplit {
+        fee_amount,
+        commission_amount,
+        keeper_tip,
+        payout_amount: gross_payout - fee_amount - commission_amount - keeper_tip,
+    })
+}
+
+pub fn validate_payout_shares(shares: &[PayoutShare]) -> Result<()> {
+    require!(
+        shares.len() <= AjoParticipant::MAX_PAYOUT_SHARES,
+        KooPaaError::InvalidPayoutShares
+    );
+    require!(
+        shares.iter().all(|share| share.bps > 0),
+        KooPaaError::InvalidPayoutShares
+    );
+    let total_bps: u32 = shares.iter().map(|share| share.bps as u32).sum();
+    require!(
+        total_bps <= PayoutShare::TOTAL_BPS as u32,
+        KooPaaError::InvalidPayoutShares
+    );
+    Ok(())
+}
+
+// Work out what each payout share receives and check `accounts` starts with
+// the share destinations in order. The recipient keeps whatever is left.
+pub fn payout_share_transfers(
+    shares: &[PayoutShare],
+    accounts: &[AccountInfo],
+    payout_amount: u64,
+) -> Result<Vec<PayoutShareTransfer>> {
+    require!(
+        accounts.len() >= shares.len(),
+        KooPaaError::InvalidPayoutShareAccount
+    );
+
+    shares
+        .iter()
+        .zip(accounts)
+        .map(|(share, account)| {
+            require_keys_eq!(
+                account.key(),
+                share.destination,
+                KooPaaError::InvalidPayoutShareAccount
+            );
+            Ok(PayoutShareTransfer {
+                destination: share.destination,
+                amount: (payout_amount as u128 * share.bps as u128 / PayoutShare::TOTAL_BPS as u128)
+                    as u64,
+            })
+        })
+        .collect()
+}
+
+// Close the group. Rounds already paid out are not refunded; each member can
+// claim whatever they contributed beyond that, prepaid rounds included.
+pub fn close_group(group: &mut AjoGroup, global_state: &mut GlobalState) {
+    let contributions_per_payout = group.payout_interval / group.contribution_interval;
+    group.refund_base_round = group.payout_round * contributions_per_payout as u16;
+
+    if group.start_timestamp.is_some() && global_state.active_groups > 0 {
+        global_state.active_groups -= 1;
+    }
+    group.is_closed = true;
+}
+
+// When a close vote cast at `now` lapses; an expiry of 0 keeps it until revoked
+pub fn close_vote_expiry(expiry_secs: u32, now: i64) -> i64 {
+    if expiry_secs == 0 {
+        i64::MAX
+    } else {
+        now + expiry_secs as i64
+    }
+}
+
+// Drop the close votes that have lapsed by `now`. Returns how many were dropped.
+pub fn expire_close_votes(group: &mut AjoGroup, now: i64) -> u8 {
+    let before = group.close_vote_expiries.len();
+    group
+        .close_vote_expiries
+        .retain(|expires_at| *expires_at >= now);
+    (before - group.close_vote_expiries.len()) as u8
+}
+
+// Whether the member's close vote is still counted at `now`
+pub fn has_live_close_vote(participant: &AjoParticipant, now: i64) -> bool {
+    participant
+        .close_vote_expires_at
+        .is_some_and(|expires_at| expires_at >= now)
+}
+
+// Record the member's close vote, lapsing at `expires_at`
+pub fn cast_close_vote(group: &mut AjoGroup, participant: &mut AjoParticipant, expires_at: i64) {
+    participant.close_vote_expires_at = Some(expires_at);
+    group.close_vote_expiries.push(expires_at);
+}
+
+// Take back the member's close vote. Votes lapsing at the same time are
+// interchangeable, and a lapsed one may already have been dropped.
+pub fn withdraw_close_vote(group: &mut AjoGroup, participant: &mut AjoParticipant) {
+    let Some(expires_at) = participant.close_vote_expires_at.take() else {
+        return;
+    };
+    if let Some(index) = group
+        .close_vote_expiries
+        .iter()
+        .position(|entry| *entry == expires_at)
+    {
+        group.close_vote_expiries.swap_remove(index);
+    }
+}
+
+// More than half of the members holding a seat want the group closed
+pub fn close_vote_passed(group: &AjoGroup) -> bool {
+    group.close_vote_expiries.len() * 2 > group.active_members as usize
+}
+
+// Free a member's seat for a replacement, withdrawing their open votes
+pub fn vacate_participant(group: &mut AjoGroup, participant: &mut AjoParticipant) {
+    participant.status = ParticipantStatus::Vacant;
+    group.active_members -= 1;
+    group.vacant_rounds += participant.contribution_round as u32;
+
+    withdraw_close_vote(group, participant);
+    if participant.pause_vote_epoch == group.pause_vote_epoch {
+        participant.pause_vote_epoch = 0;
+        group.pause_vote_count -= 1;
+    }
+}
+
+// Hand a vacated seat to a member from the standby list. Returns the arrears
+// the replacement has to pay so the group's schedule carries on unchanged.
+pub fn hand_over_seat(
+    group: &mut AjoGroup,
+    vacated: &mut AjoParticipant,
+    replacement: &mut AjoParticipant,
+    current_round: u16,
+) -> Result<u64> {
+    require!(
+        vacated.status == ParticipantStatus::Vacant,
+        KooPaaError::SeatNotVacant
+    );
+    require!(
+        replacement.status == ParticipantStatus::Pending,
+        KooPaaError::NotOnStandby
+    );
+    // A replacement would owe contributions for a payout they never receive
+    require!(
+        (vacated.seat as u16) >= group.payout_round,
+        KooPaaError::SeatAlreadyPaidOut
+    );
+
+    // Installments the previous member paid stay with the seat
+    let seat_round = vacated.contribution_round;
+    let rounds_owed = current_round.saturating_sub(seat_round);
+    let (arrears, partial_amount) = if rounds_owed > 0 {
+        (
+            group.contribution_amount * rounds_owed as u64 - vacated.partial_amount,
+            0,
+        )
+    } else {
+        (0, vacated.partial_amount)
+    };
+
+    let new_round = seat_round.max(current_round);
+    group.move_seat_round(seat_round, new_round);
+    group.vacant_rounds -= seat_round as u32;
+    group.pending_requests -= 1;
+    group.active_members += 1;
+
+    vacated.status = ParticipantStatus::Left;
+    vacated.partial_amount = 0;
+
+    replacement.status = ParticipantStatus::Active;
+    replacement.seat = vacated.seat;
+    replacement.first_proposal = group.proposal_count;
+    replacement.contribution_round = new_round;
+    replacement.partial_amount = partial_amount;
+
+    Ok(arrears)
+}
+
+// Move a hardship requester to the next payout seat, swapping places with the
+// member who held it. Returns the requester's former seat.
+pub fn swap_payout_seats(
+    group: &AjoGroup,
+    requester: &mut AjoParticipant,
+    displaced: &mut AjoParticipant,
+) -> Result<u8> {
+    // The rotation may have moved on since the request
+    let next_seat = (group.payout_round % group.num_participants as u16) as u8;
+    require!(
+        requester.is_active() && requester.seat as u16 > group.payout_round,
+        KooPaaError::NotEligibleForHardshipPayout
+    );
+    // Only the seat's current holder can give it up; a record that left or
+    // never took a seat would leave two members on one seat
+    require!(
+        displaced.is_active() && displaced.seat == next_seat,
+        KooPaaError::InvalidParticipantAccount
+    );
+
+    let former_seat = requester.seat;
+    requester.seat = next_seat;
+    displaced.seat = former_seat;
+    Ok(former_seat)
+}
+
+// The admin fills vacant seats directly; anyone else needs an approved
+// `FillSeat` proposal for this seat and replacement, which is used up here.
+// Returns whether a proposal was used.
+pub fn authorize_seat_fill(
+    group: &AjoGroup,
+    group_key: Pubkey,
+    caller: Pubkey,
+    proposal: Option<&mut Proposal>,
+    seat: u8,
+    replacement: Pubkey,
+    now: i64,
+) -> Result<bool> {
+    let Some(proposal) = proposal else {
+        require!(group.admin == caller, KooPaaError::OnlyAdminCanUpdate);
+        return Ok(false);
+    };
+
+    require!(
+        proposal.group == group_key
+            && proposal.kind == (ProposalKind::FillSeat { seat, replacement }),
+        KooPaaError::ProposalMismatch
+    );
+    require!(!proposal.executed, KooPaaError::ProposalAlreadyExecuted);
+    require!(proposal.is_approved(now), KooPaaError::ProposalNotApproved);
+
+    proposal.executed = true;
+    Ok(true)
+}
+
+// Change the terms of a group that has not started yet, validated like a new group's
+pub fn update_group_terms(
+    group: &mut AjoGroup,
+    limits: &GroupLimits,
+    terms: GroupTerms,
+    num_participants: u8,
+) -> Result<()> {
+    let GroupTerms {
+        contribution_amount,
+        contribution_interval,
+        payout_interval,
+        time_unit,
+    } = terms;
+    require!(
+        group.start_timestamp.is_none(),
+        KooPaaError::GroupAlreadyStarted
+    );
+    validate_group_params(
+        limits,
+        contribution_amount,
+        contribution_interval,
+        payout_interval,
+        num_participants,
+        time_unit,
+    )?;
+
+    // The group starts as soon as it is full, so it must still have an open seat
+    require!(
+        num_participants > group.seats_filled,
+        KooPaaError::InvalidParticipantCount
+    );
+
+    group.contribution_amount = contribution_amount;
+    group.contribution_interval = contribution_interval;
+    group.payout_interval = round_payout_interval(contribution_interval, payout_interval);
+    group.time_unit = time_unit;
+    group.num_participants = num_participants;
+    Ok(())
+}
+
+// Validate the economic parameters of a group against the protocol limits
+pub fn validate_group_params(
+    limits: &GroupLimits,
+    contribution_amount: u64,
+    contribution_interval: u8,
+    payout_interval: u8,
+    num_participants: u8,
+    time_unit: TimeUnit,
+) -> Result<()> {
+    require!(
+        contribution_amount > 0,
+        KooPaaError::InvalidContributionAmount
+    );
+
+    if let TimeUnit::Months { day_of_month } = time_unit {
+        require!(
+            (1..=31).contains(&day_of_month),
+            KooPaaError::InvalidInterval
+        );
+    }
+
+    // The day based limits cap every unit; sub-day units replace their lower
+    // bounds with the protocol's minimum interval length
+    let day = interval_to_seconds(1, TimeUnit::Days);
+    let contribution_secs = interval_to_seconds(contribution_interval as u16, time_unit);
+    let payout_secs = interval_to_seconds(payout_interval as u16, time_unit);
+    require!(
+        contribution_secs <= limits.max_contribution_interval as i64 * day
+            && payout_secs <= limits.max_payout_interval as i64 * day,
+        KooPaaError::InvalidInterval
+    );
+    if time_unit.is_sub_day() {
+        require!(
+            limits.min_interval_secs > 0
+                && contribution_interval > 0
+                && contribution_secs >= limits.min_interval_secs as i64,
+            KooPaaError::InvalidInterval
+        );
+    } else {
+        require!(
+            contribution_secs >= limits.min_contribution_interval as i64 * day
+                && payout_secs >= limits.min_payout_interval as i64 * day,
+            KooPaaError::InvalidInterval
+        );
+    }
+    require!(
+        payout_interval >= contribution_interval,
+        KooPaaError::InvalidInterval
+    );
+    require!(
+        (limits.min_participants..=limits.max_participants).contains(&num_participants),
+        KooPaaError::InvalidParticipantCount
+    );
+    Ok(())
+}
+
+// Round the payout interval up to a whole number of contribution intervals
+pub fn round_payout_interval(contribution_interval: u8, payout_interval: u8) -> u8 {
+    let interval = payout_interval as f64 / contribution_interval as f64;
+    interval.ceil() as u8 * contribution_interval
+}
+
+// Validate protocol configuration supplied by the authority
+pub fn validate_protocol_config(config: &ProtocolConfig) -> Result<()> {
+    require!(
+        config.fee_percentage <= ProtocolConfig::MAX_FEE_PERCENTAGE,
+        KooPaaError::InvalidFeePercentage
+    );
+    require!(
+        config.max_admin_commission <= ProtocolConfig::MAX_ADMIN_COMMISSION,
+        KooPaaError::InvalidAdminCommission
+    );
+    require!(
+        config.keeper_tip <= ProtocolConfig::MAX_KEEPER_TIP,
+        KooPaaError::InvalidKeeperTip
+    );
+    require!(config.limits.is_valid(), KooPaaError::InvalidProtocolConfig);
+    Ok(())
+}
+
+// Grow or shrink a program-owned account to `new_size`, with `payer` topping up
+// rent when it grows. Lamports freed by shrinking stay with the account.
+pub fn resize_account<'info>(
+    account: &AccountInfo<'info>,
+    payer: &AccountInfo<'info>,
+    system_program: &AccountInfo<'info>,
+    new_size: usize,
+) -> Result<()> {
+    let required_lamports = Rent::get()?.minimum_balance(new_size);
+    let current_lamports = account.lamports();
+
+    if required_lamports > current_lamports {
+        system_program::transfer(
+            CpiContext::new(
+                system_program.clone(),
+                system_program::Transfer {
+                    from: payer.clone(),
+                    to: account.clone(),
+                },
+            ),
+            required_lamports - current_lamports,
+        )?;
+    }
+
+    account.realloc(new_size, false)?;
+    Ok(())
+}
+
+// Create a program-owned PDA, tolerating lamports already sent to its address
+pub fn create_pda_account<'info>(
+    account: &AccountInfo<'info>,
+    payer: &AccountInfo<'info>,
+    system_program: &AccountInfo<'info>,
+    space: usize,
+    signer_seeds: &[&[u8]],
+) -> Result<()> {
+    let required_lamports = Rent::get()?.minimum_balance(space);
+    let current_lamports = account.lamports();
+
+    if current_lamports == 0 {
+        system_program::create_account(
+            CpiContext::new_with_signer(
+                system_program.clone(),
+                system_program::CreateAccount {
+                    from: payer.clone(),
+                    to: account.clone(),
+                },
+                &[signer_seeds],
+            ),
+            required_lamports,
+            space as u64,
+            &crate::ID,
+        )?;
+        return Ok(());
+    }
+
+    if required_lamports > current_lamports {
+        system_program::transfer(
+            CpiContext::new(
+                system_program.clone(),
+                system_program::Transfer {
+                    from: payer.clone(),
+                    to: account.clone(),
+                },
+            ),
+            required_lamports - current_lamports,
+        )?;
+    }
+    system_program::allocate(
+        CpiContext::new_with_signer(
+            system_program.clone(),
+            system_program::Allocate {
+                account_to_allocate: account.clone(),
+            },
+            &[signer_seeds],
+        ),
+        space as u64,
+    )?;
+    system_program::assign(
+        CpiContext::new_with_signer(
+            system_program.clone(),
+            system_program::Assign {
+                account_to_assign: account.clone(),
+            },
+            &[signer_seeds],
+        ),
+        &crate::ID,
+    )?;
+    Ok(())
+}
+
+// Token-2022 extensions the group accounting can cope with. Transfer fees are
+// covered by grossing up deposits; the metadata extensions carry no behavior.
+const SUPPORTED_MINT_EXTENSIONS: [ExtensionType; 3] = [
+    ExtensionType::TransferFeeConfig,
+    ExtensionType::MetadataPointer,
+    ExtensionType::TokenMetadata,
+];
+
+// Reject Token-2022 mints whose extensions could move or freeze vault funds
+// behind the program's back (transfer hooks, permanent delegates, ...)
+pub fn validate_mint_extensions(mint: &AccountInfo) -> Result<()> {
+    if *mint.owner != spl_token_2022::ID {
+        return Ok(());
+    }
+
+    let data = mint.try_borrow_data()?;
+    let mint = StateWithExtensions::<spl_token_2022::state::Mint>::unpack(&data)?;
+    let supported = mint
+        .get_extension_types()?
+        .iter()
+        .all(|extension| SUPPORTED_MINT_EXTENSIONS.contains(extension));
+    require!(supported, KooPaaError::UnsupportedMintExtension);
+
+    Ok(())
+}
+
+// Amount to send so the destination is credited with `amount` once the
+// mint's transfer fee (if any) has been withheld
+pub fn amount_with_transfer_fee(mint: &AccountInfo, amount: u64) -> Result<u64> {
+    if *mint.owner != spl_token_2022::ID {
+        return Ok(amount);
+    }
+
+    let data = mint.try_borrow_data()?;
+    let mint = StateWithExtensions::<spl_token_2022::state::Mint>::unpack(&data)?;
+    let Ok(fee_config) = mint.get_extension::<TransferFeeConfig>() else {
+        return Ok(amount);
+    };
+
+    let fee = fee_config
+        .calculate_inverse_epoch_fee(Clock::get()?.epoch, amount)
+        .ok_or(KooPaaError::MathOverflow)?;
+    amount
+        .checked_add(fee)
+        .ok_or(KooPaaError::MathOverflow.into())
+}
+
+// Whether transfers of `mint` withhold a fee
+pub fn has_transfer_fee(mint: &AccountInfo) -> Result<bool> {
+    if *mint.owner != spl_token_2022::ID {
+        return Ok(false);
+    }
+
+    let data = mint.try_borrow_data()?;
+    let mint = StateWithExtensions::<spl_token_2022::state::Mint>::unpack(&data)?;
+    Ok(mint.get_extension::<TransferFeeConfig>().is_ok())
+}
+
+// Check a group may switch to `adapter`, or opt out with `None`
+pub fn validate_yield_adapter(
+    group: &AjoGroup,
+    global_state: &GlobalState,
+    adapter: Option<Pubkey>,
+) -> Result<()> {
+    require!(!group.is_native(), KooPaaError::InvalidYieldAdapter);
+    require!(group.yield_deposited == 0, KooPaaError::YieldFundsDeposited);
+    if let Some(adapter) = adapter {
+        require!(
+            global_state.yield_adapters.contains(&adapter),
+            KooPaaError::YieldAdapterNotAllowed
+        );
+    }
+    Ok(())
+}
+
+// Pull `amount`, or everything with `WITHDRAW_ALL`, back from the group's yield
+// adapter. Returns what reached the vault and how much of it is yield.
+pub fn withdraw_from_yield<'info>(
+    group: &mut AjoGroup,
+    remaining_accounts: &[AccountInfo<'info>],
+    group_vault: &GroupVault<'info>,
+    vault: &mut InterfaceAccount<'info, TokenAccount>,
+    amount: u64,
+) -> Result<(u64, u64)> {
+    let adapter_key = group
+        .yield_adapter
+        .ok_or(KooPaaError::InvalidYieldAdapter)?;
+    let adapter = YieldAdapter::from_remaining_accounts(
+        remaining_accounts,
+        adapter_key,
+        group_vault.group.key(),
+    )?;
+
+    let balance_before = vault.amount;
+    adapter.withdraw(group_vault, amount)?;
+    vault.reload()?;
+
+    let returned = vault.amount.saturating_sub(balance_before);
+    let yield_amount = group.record_yield_withdrawal(returned, amount == WITHDRAW_ALL);
+    Ok((returned, yield_amount))
+}
+
+// Send lamports from a wallet into a native SOL group
+pub fn deposit_lamports<'info>(
+    from: &AccountInfo<'info>,
+    group: &AccountInfo<'info>,
+    system_program: &AccountInfo<'info>,
+    amount: u64,
+) -> Result<()> {
+    system_program::transfer(
+        CpiContext::new(
+            system_program.clone(),
+            system_program::Transfer {
+                from: from.clone(),
+                to: group.clone(),
+            },
+        ),
+        amount,
+    )
+}
+
+// Pay lamports out of a native SOL group. The group account is owned by the
+// program, so its balance is debited directly; its rent reserve is never touched.
+pub fn withdraw_lamports(group: &AccountInfo, to: &AccountInfo, amount: u64) -> Result<()> {
+    let rent_reserve = Rent::get()?.minimum_balance(group.data_len());
+    let remaining = group
+        .lamports()
+        .checked_sub(amount)
+        .filter(|remaining| *remaining >= rent_reserve)
+        .ok_or(KooPaaError::InsufficientFunds)?;
+
+    **group.try_borrow_mut_lamports()? = remaining;
+    **to.try_borrow_mut_lamports()? = to
+        .lamports()
+        .checked_add(amount)
+        .ok_or(KooPaaError::MathOverflow)?;
+    Ok(())
 }
 
 // Calculate fee amount based on contribution
@@ -18,25 +1415,813 @@ pub fn calculate_fee(amount: u64, fee_percentage: u8) -> u64 {
     (amount * fee_percentage as u64) / 1000
 }
 
-// Calculate the total group contribution per round
-pub fn calculate_round_total(group: &AjoGroup) -> u64 {
-    // Total contribution is the contribution amount times the number of contributors
-    // (which is all participants except the current recipient)
-    group.contribution_amount * (group.participants.len() as u64 - 1)
-}
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::state::tests::{member, test_config, test_global_state, test_group};
 
-// Check if all participants have contributed for the current round
-pub fn all_contributed(group: &AjoGroup) -> bool {
-    let current_round = group.payout_round;
+    fn timestamp(year: i64, month: u32, day: u32) -> i64 {
+        days_from_civil(year, month, day) * SECONDS_PER_DAY
+    }
 
-    // Get the current recipient
-    let recipient_index = (current_round as usize) % group.participants.len();
-    let current_recipient = group.participants[recipient_index].pubkey;
+    // Intervals elapsed at `now` for a monthly group started at `start`
+    fn months_elapsed(day_of_month: u8, interval: u8, start: i64, now: i64) -> u16 {
+        let mut group = test_group(TimeUnit::Months { day_of_month }, 3);
+        group.start_timestamp = Some(start);
+        intervals_elapsed(&group, interval, now - start)
+    }
 
-    // Check if all other participants have contributed to this round
-    group
-        .participants
-        .iter()
-        .filter(|p| p.pubkey != current_recipient)
-        .all(|p| p.contribution_round >= current_round)
+    #[test]
+    fn civil_dates_round_trip() {
+        assert_eq!(days_from_civil(1970, 1, 1), 0);
+        assert_eq!(days_from_civil(1969, 12, 31), -1);
+        assert_eq!(days_from_civil(2000, 3, 1), 11_017);
+        assert_eq!(days_from_civil(2024, 2, 29), 19_782);
+
+        assert_eq!(civil_from_days(0), (1970, 1, 1));
+        assert_eq!(civil_from_days(-1), (1969, 12, 31));
+        assert_eq!(civil_from_days(19_782), (2024, 2, 29));
+
+        for days in (-150_000..150_000).step_by(97) {
+            let (year, month, day) = civil_from_days(days);
+            assert_eq!(days_from_civil(year, month, day), days);
+        }
+    }
+
+    #[test]
+    fn leap_years() {
+        assert_eq!(days_in_month(2024, 2), 29);
+        assert_eq!(days_in_month(2023, 2), 28);
+        assert_eq!(days_in_month(2000, 2), 29);
+        assert_eq!(days_in_month(1900, 2), 28);
+        assert_eq!(days_in_month(2023, 4), 30);
+        assert_eq!(days_in_month(2023, 12), 31);
+    }
+
+    #[test]
+    fn day_31_is_clamped_to_the_end_of_february() {
+        let start = timestamp(2023, 1, 31);
+        assert_eq!(months_elapsed(31, 1, start, timestamp(2023, 2, 28) - 1), 0);
+        assert_eq!(months_elapsed(31, 1, start, timestamp(2023, 2, 28)), 1);
+        assert_eq!(months_elapsed(31, 1, start, timestamp(2023, 3, 30)), 1);
+        assert_eq!(months_elapsed(31, 1, start, timestamp(2023, 3, 31)), 2);
+
+        // Leap years move the date to the 29th
+        let start = timestamp(2024, 1, 31);
+        assert_eq!(months_elapsed(31, 1, start, timestamp(2024, 2, 28)), 0);
+        assert_eq!(months_elapsed(31, 1, start, timestamp(2024, 2, 29)), 1);
+    }
+
+    #[test]
+    fn first_date_falls_after_the_start() {
+        // Starting on the chosen day waits for the next month's date
+        let start = timestamp(2024, 1, 15);
+        assert_eq!(months_elapsed(15, 1, start, start + SECONDS_PER_DAY), 0);
+        assert_eq!(months_elapsed(15, 1, start, timestamp(2024, 2, 15)), 1);
+
+        // Starting after it skips to the next month too
+        let start = timestamp(2024, 1, 20);
+        assert_eq!(months_elapsed(15, 1, start, timestamp(2024, 2, 14)), 0);
+        assert_eq!(months_elapsed(15, 1, start, timestamp(2024, 2, 15)), 1);
+
+        // Starting before it counts this month's date
+        let start = timestamp(2024, 1, 10);
+        assert_eq!(months_elapsed(15, 1, start, timestamp(2024, 1, 15) - 1), 0);
+        assert_eq!(months_elapsed(15, 1, start, timestamp(2024, 1, 15)), 1);
+        assert_eq!(months_elapsed(15, 1, start, timestamp(2024, 12, 15)), 12);
+    }
+
+    #[test]
+    fn multi_month_intervals() {
+        let start = timestamp(2024, 1, 1);
+        assert_eq!(months_elapsed(1, 2, start, timestamp(2024, 2, 1)), 0);
+        assert_eq!(months_elapsed(1, 2, start, timestamp(2024, 3, 1)), 1);
+        assert_eq!(months_elapsed(1, 2, start, timestamp(2025, 1, 1)), 6);
+    }
+
+    #[test]
+    fn skips_start_at_the_next_payout_or_after_a_running_skip() {
+        let mut group = test_group(TimeUnit::Days, 5);
+        group.payout_interval = 2;
+
+        // Halfway through the second payout interval
+        let now = 3 * SECONDS_PER_DAY;
+        let skip = schedule_skip(&mut group, now, 2).unwrap();
+        assert_eq!((skip.from_round, skip.rounds), (4, 4));
+        assert_eq!(scheduled_rounds(&group, 6 * SECONDS_PER_DAY), 4);
+        assert_eq!(scheduled_rounds(&group, 9 * SECONDS_PER_DAY), 5);
+
+        // A second skip queues up behind the one still running
+        let skip = schedule_skip(&mut group, 5 * SECONDS_PER_DAY, 1).unwrap();
+        assert_eq!((skip.from_round, skip.rounds), (8, 2));
+        assert_eq!(scheduled_rounds(&group, 10 * SECONDS_PER_DAY), 4);
+    }
+
+    #[test]
+    fn skips_are_limited_to_the_payouts_left() {
+        let mut group = test_group(TimeUnit::Days, 5);
+        group.payout_round = 3;
+
+        assert!(schedule_skip(&mut group, 0, 0).is_err());
+        assert!(schedule_skip(&mut group, 0, 3).is_err());
+        assert!(schedule_skip(&mut group, 0, 2).is_ok());
+    }
+
+    #[test]
+    fn skips_past_the_end_of_the_schedule_fail_without_panicking() {
+        let mut group = test_group(TimeUnit::Days, 255);
+        group.payout_interval = 255;
+
+        let now = 65_000 * SECONDS_PER_DAY;
+        assert!(schedule_skip(&mut group, now, 255).is_err());
+        assert!(group.schedule_skips.is_empty());
+    }
+
+    #[test]
+    fn schedule_skips_are_capped() {
+        let mut group = test_group(TimeUnit::Days, 5);
+        for _ in 0..AjoGroup::MAX_SCHEDULE_SKIPS {
+            schedule_skip(&mut group, 0, 1).unwrap();
+        }
+        assert!(schedule_skip(&mut group, 0, 1).is_err());
+    }
+
+    // A started group of five where the first `voters` seats voted to close at
+    // time 0, with votes lapsing after 100 seconds
+    fn group_with_close_votes(voters: usize) -> (AjoGroup, Vec<AjoParticipant>) {
+        let mut group = test_group(TimeUnit::Days, 5);
+        group.active_members = 5;
+        let mut members: Vec<_> = (0..5).map(|seat| member(seat, 0)).collect();
+        for member in &mut members[..voters] {
+            cast_close_vote(&mut group, member, close_vote_expiry(100, 0));
+        }
+        (group, members)
+    }
+
+    #[test]
+    fn close_votes_need_a_majority_of_active_members() {
+        let (mut group, mut members) = group_with_close_votes(2);
+        assert!(!close_vote_passed(&group));
+
+        cast_close_vote(&mut group, &mut members[2], close_vote_expiry(100, 50));
+        assert!(close_vote_passed(&group));
+    }
+
+    #[test]
+    fn close_votes_lapse_individually() {
+        let (mut group, mut members) = group_with_close_votes(2);
+        cast_close_vote(&mut group, &mut members[2], close_vote_expiry(100, 50));
+
+        assert_eq!(expire_close_votes(&mut group, 100), 0);
+        assert_eq!(expire_close_votes(&mut group, 101), 2);
+        assert_eq!(group.close_vote_expiries.len(), 1);
+        assert!(!has_live_close_vote(&members[0], 101));
+        assert!(has_live_close_vote(&members[2], 101));
+
+        // Withdrawing a lapsed vote leaves the live ones alone
+        withdraw_close_vote(&mut group, &mut members[0]);
+        assert_eq!(group.close_vote_expiries, vec![150]);
+        assert_eq!(members[0].close_vote_expires_at, None);
+    }
+
+    #[test]
+    fn close_votes_without_expiry_never_lapse() {
+        let mut group = test_group(TimeUnit::Days, 3);
+        let mut voter = member(0, 0);
+        cast_close_vote(&mut group, &mut voter, close_vote_expiry(0, 10));
+
+        assert_eq!(expire_close_votes(&mut group, i64::MAX - 1), 0);
+        assert!(has_live_close_vote(&voter, i64::MAX - 1));
+    }
+
+    #[test]
+    fn revoked_close_votes_stop_counting() {
+        let (mut group, mut members) = group_with_close_votes(3);
+        withdraw_close_vote(&mut group, &mut members[1]);
+
+        assert_eq!(group.close_vote_expiries.len(), 2);
+        assert!(!close_vote_passed(&group));
+        assert!(!has_live_close_vote(&members[1], 0));
+    }
+
+    #[test]
+    fn leaving_members_shrink_the_majority_needed() {
+        let (mut group, mut members) = group_with_close_votes(2);
+
+        // A voter leaving takes their vote along
+        vacate_participant(&mut group, &mut members[0]);
+        assert_eq!(group.close_vote_expiries.len(), 1);
+
+        // Two members leaving turns the remaining votes into a majority
+        let (mut group, mut members) = group_with_close_votes(2);
+        vacate_participant(&mut group, &mut members[3]);
+        vacate_participant(&mut group, &mut members[4]);
+        assert!(close_vote_passed(&group));
+    }
+
+    fn terms(contribution_amount: u64) -> GroupTerms {
+        GroupTerms {
+            contribution_amount,
+            contribution_interval: 7,
+            payout_interval: 14,
+            time_unit: TimeUnit::Days,
+        }
+    }
+
+    #[test]
+    fn terms_change_only_before_the_start() {
+        let mut group = test_group(TimeUnit::Days, 5);
+        let limits = GroupLimits::DEFAULT;
+
+        update_group_terms(&mut group, &limits, terms(250), 5).unwrap();
+        assert_eq!(group.contribution_amount, 250);
+        assert_eq!(
+            (group.contribution_interval, group.payout_interval),
+            (7, 14)
+        );
+
+        // Validated like a new group, with a seat still open
+        assert!(update_group_terms(&mut group, &limits, terms(0), 5).is_err());
+        assert!(update_group_terms(&mut group, &limits, terms(250), 1).is_err());
+
+        group.start_timestamp = Some(0);
+        assert!(update_group_terms(&mut group, &limits, terms(300), 5).is_err());
+        assert_eq!(group.contribution_amount, 250);
+    }
+
+    fn fill_seat_proposal(group_key: Pubkey, seat: u8, replacement: Pubkey) -> Proposal {
+        Proposal {
+            group: group_key,
+            id: 0,
+            proposer: Pubkey::new_unique(),
+            kind: ProposalKind::FillSeat { seat, replacement },
+            reason: String::new(),
+            deadline: 100,
+            electorate: 3,
+            quorum: 50,
+            threshold: 50,
+            yes_votes: 2,
+            no_votes: 0,
+            abstain_votes: 0,
+            executed: false,
+            bumps: 0,
+        }
+    }
+
+    #[test]
+    fn admin_fills_seats_without_a_vote() {
+        let group = test_group(TimeUnit::Days, 3);
+        let (group_key, replacement) = (Pubkey::new_unique(), Pubkey::new_unique());
+
+        assert!(
+            !authorize_seat_fill(&group, group_key, group.admin, None, 1, replacement, 0).unwrap()
+        );
+        assert!(
+            authorize_seat_fill(&group, group_key, replacement, None, 1, replacement, 0).is_err()
+        );
+    }
+
+    // Fill `seat` in a fresh group as `replacement`, authorized by `proposal`
+    fn fill_by_vote(
+        proposal: &mut Proposal,
+        group_key: Pubkey,
+        seat: u8,
+        replacement: Pubkey,
+    ) -> Result<bool> {
+        let group = test_group(TimeUnit::Days, 3);
+        authorize_seat_fill(
+            &group,
+            group_key,
+            replacement,
+            Some(proposal),
+            seat,
+            replacement,
+            0,
+        )
+    }
+
+    #[test]
+    fn approved_fill_proposals_are_used_once() {
+        let (group_key, replacement) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let mut proposal = fill_seat_proposal(group_key, 1, replacement);
+
+        assert!(fill_by_vote(&mut proposal, group_key, 1, replacement).unwrap());
+        assert!(proposal.executed);
+        assert!(fill_by_vote(&mut proposal, group_key, 1, replacement).is_err());
+    }
+
+    #[test]
+    fn fill_proposals_only_cover_their_seat_and_replacement() {
+        let (group_key, replacement) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let mut proposal = fill_seat_proposal(group_key, 1, replacement);
+
+        assert!(fill_by_vote(&mut proposal, group_key, 2, replacement).is_err());
+        assert!(fill_by_vote(&mut proposal, group_key, 1, Pubkey::new_unique()).is_err());
+        assert!(fill_by_vote(&mut proposal, Pubkey::new_unique(), 1, replacement).is_err());
+
+        // Nor before the vote has passed
+        proposal.yes_votes = 1;
+        assert!(fill_by_vote(&mut proposal, group_key, 1, replacement).is_err());
+        assert!(!proposal.executed);
+    }
+
+    #[test]
+    fn hardship_swaps_with_the_next_seat() {
+        let mut group = test_group(TimeUnit::Days, 4);
+        group.payout_round = 1;
+        let mut requester = member(3, 1);
+        let mut displaced = member(1, 1);
+
+        assert_eq!(
+            swap_payout_seats(&group, &mut requester, &mut displaced).unwrap(),
+            3
+        );
+        assert_eq!((requester.seat, displaced.seat), (1, 3));
+
+        // Seats already paid out are not eligible
+        let mut requester = member(0, 1);
+        let mut displaced = member(1, 1);
+        assert!(swap_payout_seats(&group, &mut requester, &mut displaced).is_err());
+    }
+
+    #[test]
+    fn hardship_only_displaces_an_active_member() {
+        let mut group = test_group(TimeUnit::Days, 4);
+        group.payout_round = 1;
+
+        for status in [
+            ParticipantStatus::Left,
+            ParticipantStatus::Vacant,
+            ParticipantStatus::Pending,
+        ] {
+            let mut requester = member(3, 1);
+            let mut displaced = AjoParticipant {
+                status,
+                ..member(1, 1)
+            };
+            assert!(swap_payout_seats(&group, &mut requester, &mut displaced).is_err());
+            assert_eq!((requester.seat, displaced.seat), (3, 1));
+        }
+    }
+
+    #[test]
+    fn fixed_units_divide_elapsed_time() {
+        let group = test_group(TimeUnit::Days, 3);
+        assert_eq!(intervals_elapsed(&group, 7, 14 * SECONDS_PER_DAY - 1), 1);
+        assert_eq!(intervals_elapsed(&group, 7, 14 * SECONDS_PER_DAY), 2);
+    }
+
+    #[test]
+    fn rounds_count_from_the_start() {
+        let mut group = test_group(TimeUnit::Days, 3);
+        let global_state = test_global_state();
+        group.contribution_interval = 7;
+        assert_eq!(current_contribution_round(&group, &global_state, 0), None);
+
+        group.start_timestamp = Some(1_000);
+        let week = interval_to_seconds(7, TimeUnit::Days);
+        let round_at = |now| current_contribution_round(&group, &global_state, now);
+        assert_eq!(round_at(1_000), Some(0));
+        assert_eq!(round_at(1_000 + week - 1), Some(0));
+        assert_eq!(round_at(1_000 + 2 * week), Some(2));
+    }
+
+    #[test]
+    fn paused_time_does_not_count_toward_the_schedule() {
+        let mut group = test_group(TimeUnit::Days, 3);
+        let mut global_state = test_global_state();
+        group.start_timestamp = Some(0);
+
+        // The group paused at 100 and resumed at 300
+        group.paused_at = Some(100);
+        assert_eq!(
+            active_time_since_start(&group, &global_state, 250),
+            Some(100)
+        );
+        group.paused_at = None;
+        group.paused_secs = 200;
+        assert_eq!(
+            active_time_since_start(&group, &global_state, 500),
+            Some(300)
+        );
+
+        // The protocol has been paused since 400
+        global_state.paused = true;
+        global_state.paused_at = 400;
+        assert_eq!(
+            active_time_since_start(&group, &global_state, 600),
+            Some(200)
+        );
+    }
+
+    #[test]
+    fn overlapping_pauses_count_once() {
+        let mut group = test_group(TimeUnit::Days, 3);
+        let mut global_state = test_global_state();
+        group.start_timestamp = Some(0);
+
+        // The protocol paused from 100 to 200, the group from 150 until now
+        global_state.total_paused_secs = 100;
+        group.paused_at = Some(150);
+        group.pause_protocol_snapshot = 50;
+        assert_eq!(
+            active_time_since_start(&group, &global_state, 300),
+            Some(100)
+        );
+    }
+
+    #[test]
+    fn protocol_pauses_before_the_start_are_ignored() {
+        let mut group = test_group(TimeUnit::Days, 3);
+        let mut global_state = test_global_state();
+        global_state.total_paused_secs = 500;
+        group.start_timestamp = Some(1_000);
+        group.protocol_pause_offset = 500;
+        assert_eq!(
+            active_time_since_start(&group, &global_state, 1_100),
+            Some(100)
+        );
+    }
+
+    #[test]
+    fn group_params_follow_the_protocol_limits() {
+        let limits = GroupLimits::DEFAULT;
+        assert!(validate_group_params(&limits, 100, 7, 14, 5, TimeUnit::Days).is_ok());
+
+        assert!(validate_group_params(&limits, 0, 7, 14, 5, TimeUnit::Days).is_err());
+        assert!(validate_group_params(&limits, 100, 14, 7, 5, TimeUnit::Days).is_err());
+        assert!(validate_group_params(
+            &limits,
+            100,
+            7,
+            14,
+            limits.max_participants + 1,
+            TimeUnit::Days
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn interval_limits_apply_across_time_units() {
+        let mut limits = GroupLimits::DEFAULT;
+        assert!(validate_group_params(&limits, 100, 1, 2, 5, TimeUnit::Weeks).is_ok());
+        assert!(validate_group_params(&limits, 100, 1, 20, 5, TimeUnit::Weeks).is_err());
+
+        // Sub-day units stay disabled until the protocol sets a minimum
+        assert!(validate_group_params(&limits, 100, 1, 2, 5, TimeUnit::Hours).is_err());
+        limits.min_interval_secs = 60 * 60;
+        assert!(validate_group_params(&limits, 100, 1, 2, 5, TimeUnit::Hours).is_ok());
+        assert!(validate_group_params(&limits, 100, 60, 120, 5, TimeUnit::Seconds).is_err());
+
+        let months = |day_of_month| TimeUnit::Months { day_of_month };
+        assert!(validate_group_params(&limits, 100, 1, 1, 5, months(31)).is_ok());
+        assert!(validate_group_params(&limits, 100, 1, 1, 5, months(0)).is_err());
+        assert!(validate_group_params(&limits, 100, 1, 1, 5, months(32)).is_err());
+    }
+
+    #[test]
+    fn payout_intervals_round_up_to_whole_contribution_intervals() {
+        assert_eq!(round_payout_interval(7, 14), 14);
+        assert_eq!(round_payout_interval(7, 10), 14);
+        assert_eq!(round_payout_interval(3, 7), 9);
+    }
+
+    #[test]
+    fn fees_are_charged_in_tenths_of_a_percent() {
+        assert_eq!(calculate_fee(10_000, 1), 10);
+        assert_eq!(
+            calculate_fee(10_000, ProtocolConfig::MAX_FEE_PERCENTAGE),
+            1_000
+        );
+
+        // Rounded down, in the member's favour
+        assert_eq!(calculate_fee(999, 1), 0);
+    }
+
+    #[test]
+    fn protocol_config_is_bounded() {
+        assert!(validate_protocol_config(&test_config()).is_ok());
+
+        let fee_too_high = ProtocolConfig {
+            fee_percentage: ProtocolConfig::MAX_FEE_PERCENTAGE + 1,
+            ..test_config()
+        };
+        assert!(validate_protocol_config(&fee_too_high).is_err());
+
+        let tip_too_high = ProtocolConfig {
+            keeper_tip: ProtocolConfig::MAX_KEEPER_TIP + 1,
+            ..test_config()
+        };
+        assert!(validate_protocol_config(&tip_too_high).is_err());
+
+        let mut limits = GroupLimits::DEFAULT;
+        limits.min_participants = limits.max_participants + 1;
+        let limits_inverted = ProtocolConfig {
+            limits,
+            ..test_config()
+        };
+        assert!(validate_protocol_config(&limits_inverted).is_err());
+    }
+
+    #[test]
+    fn contributions_catch_up_on_missed_rounds() {
+        let group = test_group(TimeUnit::Days, 3);
+        assert_eq!(
+            contribution_due(&group, &member(1, 1), 3, 0).unwrap(),
+            (3, 200)
+        );
+        assert!(contribution_due(&group, &member(1, 3), 3, 0).is_err());
+    }
+
+    #[test]
+    fn prepayments_stop_at_the_end_of_the_cycle() {
+        let group = test_group(TimeUnit::Days, 3);
+        assert_eq!(group.total_contribution_rounds(), 3);
+        assert_eq!(
+            contribution_due(&group, &member(1, 1), 1, 2).unwrap(),
+            (3, 200)
+        );
+        // Members already paid up can still pay ahead
+        assert_eq!(
+            contribution_due(&group, &member(1, 2), 2, 1).unwrap(),
+            (3, 100)
+        );
+        assert!(contribution_due(&group, &member(1, 1), 2, 2).is_err());
+    }
+
+    #[test]
+    fn installments_complete_a_round_once_they_add_up() {
+        let mut group = test_group(TimeUnit::Days, 3);
+        let mut admin = member(0, 0);
+
+        apply_installment(&mut group, &mut admin, 1, 40).unwrap();
+        assert_eq!((admin.contribution_round, admin.partial_amount), (0, 40));
+        assert_eq!(contribution_due(&group, &admin, 1, 0).unwrap(), (1, 60));
+
+        // Overpaying the round is refused, the exact remainder completes it
+        assert!(apply_installment(&mut group, &mut admin, 1, 61).is_err());
+        assert!(apply_installment(&mut group, &mut admin, 1, 0).is_err());
+        apply_installment(&mut group, &mut admin, 1, 60).unwrap();
+        assert_eq!((admin.contribution_round, admin.partial_amount), (1, 0));
+        assert_eq!(group.min_contribution_round(), Some(1));
+    }
+
+    #[test]
+    fn installments_cannot_run_past_the_cycle() {
+        let mut group = test_group(TimeUnit::Days, 3);
+        let mut paid_up = member(0, 3);
+        assert!(apply_installment(&mut group, &mut paid_up, 3, 50).is_err());
+    }
+
+    fn share(bps: u16) -> PayoutShare {
+        PayoutShare {
+            destination: Pubkey::new_unique(),
+            bps,
+        }
+    }
+
+    #[test]
+    fn payout_shares_are_bounded() {
+        assert!(validate_payout_shares(&[]).is_ok());
+        assert!(validate_payout_shares(&[share(6_000), share(4_000)]).is_ok());
+        assert!(validate_payout_shares(&[share(6_000), share(4_001)]).is_err());
+        assert!(validate_payout_shares(&[share(1_000), share(0)]).is_err());
+        assert!(
+            validate_payout_shares(&[share(1); AjoParticipant::MAX_PAYOUT_SHARES + 1]).is_err()
+        );
+    }
+
+    #[test]
+    fn payout_shares_go_to_their_destinations_in_order() {
+        let shares = [share(2_500), share(3_333)];
+        let owner = Pubkey::new_unique();
+        let (mut lamports, mut data) = ([0u64; 2], [[0u8; 0]; 2]);
+        let [l0, l1] = &mut lamports;
+        let [d0, d1] = &mut data;
+        let first = AccountInfo::new(
+            &shares[0].destination,
+            false,
+            true,
+            l0,
+            d0,
+            &owner,
+            false,
+            0,
+        );
+        let second = AccountInfo::new(
+            &shares[1].destination,
+            false,
+            true,
+            l1,
+            d1,
+            &owner,
+            false,
+            0,
+        );
+
+        let transfers =
+            payout_share_transfers(&shares, &[first.clone(), second.clone()], 1_000).unwrap();
+        let amounts: Vec<u64> = transfers.iter().map(|t| t.amount).collect();
+        assert_eq!(amounts, vec![250, 333]);
+        assert_eq!(transfers[1].destination, shares[1].destination);
+
+        // Destinations out of order or missing are refused
+        assert!(payout_share_transfers(&shares, &[second, first.clone()], 1_000).is_err());
+        assert!(payout_share_transfers(&shares, &[first], 1_000).is_err());
+    }
+
+    #[test]
+    fn vacated_members_withdraw_their_open_votes() {
+        let mut group = test_group(TimeUnit::Days, 3);
+        group.active_members = 3;
+        group.close_vote_expiries = vec![100, 100];
+        group.pause_vote_count = 1;
+        let mut voter = AjoParticipant {
+            close_vote_expires_at: Some(100),
+            pause_vote_epoch: group.pause_vote_epoch,
+            ..member(1, 1)
+        };
+
+        vacate_participant(&mut group, &mut voter);
+        assert!(voter.status == ParticipantStatus::Vacant);
+        assert_eq!(voter.close_vote_expires_at, None);
+        assert_eq!(group.active_members, 2);
+        assert_eq!(group.close_vote_expiries, vec![100]);
+        assert_eq!(group.pause_vote_count, 0);
+    }
+
+    #[test]
+    fn closing_refunds_everything_beyond_the_rounds_paid_out() {
+        let mut group = test_group(TimeUnit::Days, 3);
+        let mut global_state = test_global_state();
+        group.payout_interval = 2;
+        group.payout_round = 1;
+        group.start_timestamp = Some(0);
+        global_state.active_groups = 1;
+
+        close_group(&mut group, &mut global_state);
+        assert!(group.is_closed);
+        assert_eq!(group.refund_base_round, 2);
+        assert_eq!(group.refund_amount(&member(1, 5)), 300);
+        assert_eq!(global_state.active_groups, 0);
+    }
+
+    #[test]
+    fn groups_only_switch_to_approved_adapters_with_nothing_deposited() {
+        let mut group = test_group(TimeUnit::Days, 3);
+        let mut global_state = test_global_state();
+        let adapter = Pubkey::new_unique();
+        assert!(validate_yield_adapter(&group, &global_state, Some(adapter)).is_err());
+
+        global_state.yield_adapters = vec![adapter];
+        assert!(validate_yield_adapter(&group, &global_state, Some(adapter)).is_ok());
+
+        group.yield_deposited = 1;
+        assert!(validate_yield_adapter(&group, &global_state, None).is_err());
+
+        group.yield_deposited = 0;
+        group.mint = AjoGroup::NATIVE_MINT;
+        assert!(validate_yield_adapter(&group, &global_state, Some(adapter)).is_err());
+    }
+
+    // A group of three with a member on standby and seat 1 vacated after
+    // paying `seat_round` rounds
+    fn group_with_vacant_seat(seat_round: u16) -> (AjoGroup, AjoParticipant, AjoParticipant) {
+        let mut group = test_group(TimeUnit::Days, 3);
+        group.add_seat_round(seat_round);
+        group.vacant_rounds = seat_round as u32;
+        group.pending_requests = 1;
+        let vacated = AjoParticipant {
+            status: ParticipantStatus::Vacant,
+            ..member(1, seat_round)
+        };
+        let standby = AjoParticipant::new(Pubkey::new_unique(), Pubkey::new_unique(), 0);
+        (group, vacated, standby)
+    }
+
+    #[test]
+    fn replacements_pay_the_rounds_the_seat_missed() {
+        let (mut group, mut vacated, mut standby) = group_with_vacant_seat(1);
+        group.proposal_count = 2;
+        assert_eq!(
+            hand_over_seat(&mut group, &mut vacated, &mut standby, 3).unwrap(),
+            200
+        );
+
+        assert!(vacated.status == ParticipantStatus::Left);
+        assert!(standby.is_active());
+        assert_eq!((standby.seat, standby.contribution_round), (1, 3));
+        // Proposals made before they took the seat are not theirs to vote on
+        assert_eq!(standby.first_proposal, 2);
+        assert_eq!((group.active_members, group.pending_requests), (2, 0));
+        assert_eq!(group.vacant_rounds, 0);
+        assert_eq!(group.min_contribution_round(), Some(0));
+    }
+
+    #[test]
+    fn seats_paid_ahead_are_handed_over_without_arrears() {
+        let (mut group, mut vacated, mut standby) = group_with_vacant_seat(4);
+        assert_eq!(
+            hand_over_seat(&mut group, &mut vacated, &mut standby, 3).unwrap(),
+            0
+        );
+        assert_eq!(standby.contribution_round, 4);
+    }
+
+    #[test]
+    fn installments_stay_with_the_seat() {
+        let (mut group, mut vacated, mut standby) = group_with_vacant_seat(1);
+        vacated.partial_amount = 40;
+        assert_eq!(
+            hand_over_seat(&mut group, &mut vacated, &mut standby, 3).unwrap(),
+            160
+        );
+        assert_eq!(standby.partial_amount, 0);
+
+        let (mut group, mut vacated, mut standby) = group_with_vacant_seat(3);
+        vacated.partial_amount = 40;
+        assert_eq!(
+            hand_over_seat(&mut group, &mut vacated, &mut standby, 3).unwrap(),
+            0
+        );
+        assert_eq!((vacated.partial_amount, standby.partial_amount), (0, 40));
+    }
+
+    #[test]
+    fn seats_already_paid_out_are_not_handed_over() {
+        let (mut group, mut vacated, mut standby) = group_with_vacant_seat(3);
+        group.payout_round = 2;
+        assert!(hand_over_seat(&mut group, &mut vacated, &mut standby, 3).is_err());
+    }
+
+    #[test]
+    fn only_vacant_seats_go_to_members_on_standby() {
+        let (mut group, mut vacated, mut standby) = group_with_vacant_seat(1);
+        let mut seated = member(2, 1);
+        assert!(hand_over_seat(&mut group, &mut seated, &mut standby, 3).is_err());
+        assert!(hand_over_seat(&mut group, &mut vacated, &mut seated, 3).is_err());
+        assert!(vacated.status == ParticipantStatus::Vacant);
+    }
+
+    // A started group of three, paid up for its first payout
+    fn group_due_a_payout() -> (AjoGroup, GlobalState) {
+        let mut group = test_group(TimeUnit::Days, 3);
+        group.start_timestamp = Some(0);
+        group.contribution_tally = vec![RoundTally { round: 1, seats: 3 }];
+        (group, test_global_state())
+    }
+
+    #[test]
+    fn payouts_go_to_each_seat_in_turn_once_due() {
+        let (mut group, global_state) = group_due_a_payout();
+        let now = interval_to_seconds(1, TimeUnit::Days);
+
+        let split = payout_split(&group, &global_state, &member(0, 1), now, false).unwrap();
+        assert_eq!(split.payout_amount, 300);
+        assert!(payout_split(&group, &global_state, &member(1, 1), now, false).is_err());
+        assert!(payout_split(&group, &global_state, &member(0, 1), now - 1, false).is_err());
+
+        // Not before every seat has paid the round
+        group.contribution_tally = vec![
+            RoundTally { round: 1, seats: 2 },
+            RoundTally { round: 0, seats: 1 },
+        ];
+        assert!(payout_split(&group, &global_state, &member(0, 1), now, false).is_err());
+    }
+
+    #[test]
+    fn fee_and_commission_come_out_of_the_pot() {
+        let (mut group, mut global_state) = group_due_a_payout();
+        group.contribution_amount = 10_000;
+        group.admin_commission = 20;
+        global_state.config.fee_mode = FeeMode::OnPayout;
+
+        let split = payout_split(
+            &group,
+            &global_state,
+            &member(0, 1),
+            interval_to_seconds(1, TimeUnit::Days),
+            false,
+        )
+        .unwrap();
+        assert_eq!(split.fee_amount, 150);
+        assert_eq!(split.commission_amount, 600);
+        assert_eq!(split.keeper_tip, 0);
+        assert_eq!(split.payout_amount, 29_250);
+    }
+
+    #[test]
+    fn keeper_tips_are_only_withheld_for_cranked_payouts() {
+        let (mut group, mut global_state) = group_due_a_payout();
+        group.contribution_amount = 10_000;
+        global_state.config.keeper_tip = 2;
+        let now = interval_to_seconds(1, TimeUnit::Days);
+
+        let split = payout_split(&group, &global_state, &member(0, 1), now, true).unwrap();
+        assert_eq!(split.keeper_tip, 60);
+        assert_eq!(split.payout_amount, 29_940);
+
+        let split = payout_split(&group, &global_state, &member(0, 1), now, false).unwrap();
+        assert_eq!((split.keeper_tip, split.payout_amount), (0, 30_000));
+    }
 }
@@ -0,0 +1,172 @@
+// Yield adapter interface
+//
+// A group can park idle vault funds in an approved lending/yield program. Any
+// program implementing the two instructions below can be approved as an adapter.
+// They are identified by Anchor-style discriminators (first 8 bytes of
+// sha256("global:<name>")) so adapters can be written with Anchor:
+//
+//   deposit(amount: u64)   move `amount` from the group vault into the adapter
+//   withdraw(amount: u64)  return `amount` to the group vault; `WITHDRAW_ALL`
+//                          returns the whole position including accrued yield
+//
+// Both instructions take the same accounts, in this order:
+//
+//   0. [writable] position       adapter account tracking the group's deposit
+//   1. [signer]   owner          the group's yield authority PDA
+//   2. [writable] vault          the group token vault
+//   3. [writable] reserve        adapter token account holding deposits
+//   4. []         mint
+//   5. []         token_program
+//   6.. any adapter specific accounts
+//
+// The adapter never gets the group PDA's signature: the group PDA owns the
+// vault and is the auto-debit delegate on member token accounts. Adapters are
+// called with a separate yield authority (seeds `[b"yield-authority", group]`)
+// that owns the position. For deposits the group delegates exactly `amount` of
+// the vault to it, and revokes the delegation as soon as the call returns.
+//
+// Instructions that talk to the adapter take its accounts through
+// `remaining_accounts`: the adapter program, the yield authority, the position,
+// the reserve and then the adapter specific accounts.
+
+use crate::errors::KooPaaError;
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::{
+    hash::hash,
+    instruction::{AccountMeta, Instruction},
+    program::invoke_signed,
+};
+use anchor_spl::token_interface::{approve, revoke, Approve, Revoke};
+
+pub const WITHDRAW_ALL: u64 = u64::MAX;
+
+// Group accounts every adapter call is made with
+pub struct GroupVault<'info> {
+    pub group: AccountInfo<'info>,
+    pub vault: AccountInfo<'info>,
+    pub mint: AccountInfo<'info>,
+    pub token_program: AccountInfo<'info>,
+}
+
+pub struct YieldAdapter<'a, 'info> {
+    pub program: &'a AccountInfo<'info>,
+    pub authority: &'a AccountInfo<'info>,
+    pub authority_bump: u8,
+    pub position: &'a AccountInfo<'info>,
+    pub reserve: &'a AccountInfo<'info>,
+    pub extra: &'a [AccountInfo<'info>],
+}
+
+impl<'a, 'info> YieldAdapter<'a, 'info> {
+    // Read the adapter accounts from `remaining_accounts`, checking the program
+    // is the adapter the group opted into and the authority is the group's
+    pub fn from_remaining_accounts(
+        accounts: &'a [AccountInfo<'info>],
+        adapter: Pubkey,
+        group: Pubkey,
+    ) -> Result<Self> {
+        let [program, authority, position, reserve, extra @ ..] = accounts else {
+            return err!(KooPaaError::MissingYieldAdapterAccounts);
+        };
+        require_keys_eq!(program.key(), adapter, KooPaaError::InvalidYieldAdapter);
+        require!(program.executable, KooPaaError::InvalidYieldAdapter);
+
+        let (expected_authority, authority_bump) =
+            Pubkey::find_program_address(&[b"yield-authority", group.as_ref()], &crate::ID);
+        require_keys_eq!(
+            authority.key(),
+            expected_authority,
+            KooPaaError::InvalidYieldAuthority
+        );
+
+        Ok(Self {
+            program,
+            authority,
+            authority_bump,
+            position,
+            reserve,
+            extra,
+        })
+    }
+
+    // `group_seeds` sign the vault delegation, which only lasts for the call
+    pub fn deposit(
+        &self,
+        group: &GroupVault<'info>,
+        amount: u64,
+        group_seeds: &[&[u8]],
+    ) -> Result<()> {
+        approve(
+            CpiContext::new_with_signer(
+                group.token_program.clone(),
+                Approve {
+                    to: group.vault.clone(),
+                    delegate: self.authority.clone(),
+                    authority: group.group.clone(),
+                },
+                &[group_seeds],
+            ),
+            amount,
+        )?;
+
+        self.invoke("deposit", group, amount)?;
+
+        revoke(CpiContext::new_with_signer(
+            group.token_program.clone(),
+            Revoke {
+                source: group.vault.clone(),
+                authority: group.group.clone(),
+            },
+            &[group_seeds],
+        ))
+    }
+
+    pub fn withdraw(&self, group: &GroupVault<'info>, amount: u64) -> Result<()> {
+        self.invoke("withdraw", group, amount)
+    }
+
+    fn invoke(&self, name: &str, group: &GroupVault<'info>, amount: u64) -> Result<()> {
+        let mut data = hash(format!("global:{name}").as_bytes()).to_bytes()[..8].to_vec();
+        data.extend_from_slice(&amount.to_le_bytes());
+
+        let mut accounts = vec![
+            AccountMeta::new(self.position.key(), false),
+            AccountMeta::new_readonly(self.authority.key(), true),
+            AccountMeta::new(group.vault.key(), false),
+            AccountMeta::new(self.reserve.key(), false),
+            AccountMeta::new_readonly(group.mint.key(), false),
+            AccountMeta::new_readonly(group.token_program.key(), false),
+        ];
+        let mut account_infos = vec![
+            self.position.clone(),
+            self.authority.clone(),
+            group.vault.clone(),
+            self.reserve.clone(),
+            group.mint.clone(),
+            group.token_program.clone(),
+        ];
+        for extra in self.extra {
+            accounts.push(AccountMeta {
+                pubkey: extra.key(),
+                is_signer: extra.is_signer,
+                is_writable: extra.is_writable,
+            });
+            account_infos.push(extra.clone());
+        }
+
+        let instruction = Instruction {
+            program_id: self.program.key(),
+            accounts,
+            data,
+        };
+        let group_key = group.group.key();
+        let authority_seeds: &[&[u8]] = &[
+            b"yield-authority",
+            group_key.as_ref(),
+            &[self.authority_bump],
+        ];
+        invoke_signed(&instruction, &account_infos, &[authority_seeds])?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,286 @@
+//lib.rs
+use anchor_lang::prelude::*;
+use anchor_spl::token_interface::{
+    transfer_checked, Mint, TokenAccount, TokenInterface, TransferChecked,
+};
+
+// Mock yield adapter used to exercise koopa-contract's yield integration on a
+// local validator. Deposits are pooled in one reserve per mint and each owner's
+// position simply tracks its redeemable value; `accrue_yield` tops a position up
+// to simulate interest.
+
+declare_id!("Fd7zXBq8gxeSXBGqkLY8CmBzDkbdKzDREKxZ5ApwFUob");
+
+#[program]
+mod mock_yield {
+    use super::*;
+
+    pub fn initialize_reserve(_ctx: Context<InitializeReserve>) -> Result<()> {
+        Ok(())
+    }
+
+    pub fn open_position(ctx: Context<OpenPosition>, owner: Pubkey) -> Result<()> {
+        let position = &mut ctx.accounts.position;
+
+        position.owner = owner;
+        position.mint = ctx.accounts.mint.key();
+        position.value = 0;
+        position.bumps = ctx.bumps.position;
+
+        Ok(())
+    }
+
+    // Adapter interface: move `amount` from the vault, which the owner is a
+    // delegate on, into the reserve
+    pub fn deposit(ctx: Context<AdapterAccounts>, amount: u64) -> Result<()> {
+        let transfer_accounts = TransferChecked {
+            from: ctx.accounts.vault.to_account_info(),
+            mint: ctx.accounts.mint.to_account_info(),
+            to: ctx.accounts.reserve.to_account_info(),
+            authority: ctx.accounts.owner.to_account_info(),
+        };
+
+        transfer_checked(
+            CpiContext::new(
+                ctx.accounts.token_program.to_account_info(),
+                transfer_accounts,
+            ),
+            amount,
+            ctx.accounts.mint.decimals,
+        )?;
+
+        ctx.accounts.position.credit(amount);
+
+        Ok(())
+    }
+
+    // Adapter interface: return `amount` to the owner's vault, or the whole
+    // position when `amount` is u64::MAX
+    pub fn withdraw(ctx: Context<AdapterAccounts>, amount: u64) -> Result<()> {
+        let amount = ctx.accounts.position.redeem(amount)?;
+
+        let mint_key = ctx.accounts.mint.key();
+        let signer_seeds = &[b"reserve", mint_key.as_ref(), &[ctx.bumps.reserve]];
+
+        let transfer_accounts = TransferChecked {
+            from: ctx.accounts.reserve.to_account_info(),
+            mint: ctx.accounts.mint.to_account_info(),
+            to: ctx.accounts.vault.to_account_info(),
+            authority: ctx.accounts.reserve.to_account_info(),
+        };
+
+        transfer_checked(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                transfer_accounts,
+                &[signer_seeds],
+            ),
+            amount,
+            ctx.accounts.mint.decimals,
+        )?;
+
+        Ok(())
+    }
+
+    // Test helper: fund the reserve and credit the amount to a position as yield
+    pub fn accrue_yield(ctx: Context<AccrueYield>, amount: u64) -> Result<()> {
+        let transfer_accounts = TransferChecked {
+            from: ctx.accounts.funder_token_account.to_account_info(),
+            mint: ctx.accounts.mint.to_account_info(),
+            to: ctx.accounts.reserve.to_account_info(),
+            authority: ctx.accounts.funder.to_account_info(),
+        };
+
+        transfer_checked(
+            CpiContext::new(
+                ctx.accounts.token_program.to_account_info(),
+                transfer_accounts,
+            ),
+            amount,
+            ctx.accounts.mint.decimals,
+        )?;
+
+        ctx.accounts.position.credit(amount);
+
+        Ok(())
+    }
+}
+
+#[derive(Accounts)]
+pub struct InitializeReserve<'info> {
+    #[account(
+        init,
+        payer = payer,
+        seeds = [b"reserve", mint.key().as_ref()],
+        bump,
+        token::mint = mint,
+        token::authority = reserve,
+        token::token_program = token_program
+    )]
+    pub reserve: InterfaceAccount<'info, TokenAccount>,
+
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(owner: Pubkey)]
+pub struct OpenPosition<'info> {
+    #[account(
+        init,
+        payer = payer,
+        space = Position::SIZE,
+        seeds = [b"position", owner.as_ref()],
+        bump
+    )]
+    pub position: Account<'info, Position>,
+
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+// Account order shared by every yield adapter, see koopa-contract's `yield_adapter` module
+#[derive(Accounts)]
+pub struct AdapterAccounts<'info> {
+    #[account(
+        mut,
+        seeds = [b"position", owner.key().as_ref()],
+        bump = position.bumps,
+        has_one = owner,
+        has_one = mint
+    )]
+    pub position: Account<'info, Position>,
+
+    pub owner: Signer<'info>,
+
+    #[account(mut, token::mint = mint)]
+    pub vault: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        seeds = [b"reserve", mint.key().as_ref()],
+        bump
+    )]
+    pub reserve: InterfaceAccount<'info, TokenAccount>,
+
+    pub mint: InterfaceAccount<'info, Mint>,
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+#[derive(Accounts)]
+pub struct AccrueYield<'info> {
+    #[account(
+        mut,
+        seeds = [b"position", position.owner.as_ref()],
+        bump = position.bumps,
+        has_one = mint
+    )]
+    pub position: Account<'info, Position>,
+
+    #[account(
+        mut,
+        seeds = [b"reserve", mint.key().as_ref()],
+        bump
+    )]
+    pub reserve: InterfaceAccount<'info, TokenAccount>,
+
+    pub funder: Signer<'info>,
+
+    #[account(mut, token::mint = mint)]
+    pub funder_token_account: InterfaceAccount<'info, TokenAccount>,
+
+    pub mint: InterfaceAccount<'info, Mint>,
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+#[account]
+pub struct Position {
+    pub owner: Pubkey, // Depositor, e.g. a koopa group's yield authority
+    pub mint: Pubkey,
+    pub value: u64, // Redeemable amount, principal plus accrued yield
+    pub bumps: u8,
+}
+
+impl Position {
+    pub const SIZE: usize = 8 + 32 + 32 + 8 + 1;
+
+    pub fn credit(&mut self, amount: u64) {
+        self.value += amount;
+    }
+
+    // Take `amount` out of the position, or all of it for u64::MAX. Returns the
+    // amount redeemed.
+    pub fn redeem(&mut self, amount: u64) -> Result<u64> {
+        let amount = if amount == u64::MAX {
+            self.value
+        } else {
+            amount
+        };
+        require!(amount <= self.value, MockYieldError::InsufficientPosition);
+
+        self.value -= amount;
+        Ok(amount)
+    }
+}
+
+#[error_code]
+pub enum MockYieldError {
+    #[msg("Withdrawal exceeds the position value")]
+    InsufficientPosition,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn position() -> Position {
+        Position {
+            owner: Pubkey::new_unique(),
+            mint: Pubkey::new_unique(),
+            value: 0,
+            bumps: 0,
+        }
+    }
+
+    #[test]
+    fn deposits_and_yield_add_to_the_position() {
+        let mut position = position();
+        position.credit(1_000);
+        position.credit(50);
+        assert_eq!(position.value, 1_050);
+    }
+
+    #[test]
+    fn partial_withdrawals_leave_the_rest() {
+        let mut position = position();
+        position.credit(1_000);
+        assert_eq!(position.redeem(400).unwrap(), 400);
+        assert_eq!(position.value, 600);
+    }
+
+    #[test]
+    fn withdraw_all_redeems_principal_and_yield() {
+        let mut position = position();
+        position.credit(1_000);
+        position.credit(75);
+        assert_eq!(position.redeem(u64::MAX).unwrap(), 1_075);
+        assert_eq!(position.value, 0);
+    }
+
+    #[test]
+    fn cannot_withdraw_more_than_the_position() {
+        let mut position = position();
+        position.credit(100);
+        assert!(position.redeem(101).is_err());
+        assert_eq!(position.value, 100);
+    }
+}
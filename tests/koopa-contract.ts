@@ -7,333 +7,219 @@ import {
   mintTo,
   getAccount,
 } from "@solana/spl-token";
-import { Koopa } from "../target/types/koopa";
+import { KoopaContract } from "../target/types/koopa_contract";
 import { expect } from "chai";
-
-describe("koopaa", () => {
+import {
+  Member,
+  contribute,
+  createMember,
+  createStartedGroup,
+  expectError,
+  participantPda,
+  setUpProtocol,
+  vaultPda,
+  voteToClose,
+  waitForNextRound,
+} from "./utils";
+
+describe("koopa-contract", () => {
   const provider = anchor.AnchorProvider.env();
   anchor.setProvider(provider);
-  const program = anchor.workspace.Koopa as Program<Koopa>;
+  const program = anchor.workspace.KoopaContract as Program<KoopaContract>;
+
+  const payer = (provider.wallet as anchor.Wallet).payer;
+  const creator = provider.wallet.publicKey;
+  const groupName = "Alpha Group";
+  const contributionAmount = 100_000;
 
   let mint: anchor.web3.PublicKey;
   let creatorTokenAccount: anchor.web3.PublicKey;
-  let participantTokenAccount: anchor.web3.PublicKey;
-  let vaultAccount: anchor.web3.PublicKey;
-  let groupPda: anchor.web3.PublicKey;
-  let groupVaultPda: anchor.web3.PublicKey;
-  let globalStatePda: anchor.web3.PublicKey;
-  let groupName = "Alpha Group";
-
-  const creator = provider.wallet;
-  const participant = anchor.web3.Keypair.generate();
+  let globalState: anchor.web3.PublicKey;
+  let group: anchor.web3.PublicKey;
+  let member: Member;
+  let thirdMember: Member;
 
   before(async () => {
-    // Mint setup
-    mint = await createMint(
-      provider.connection,
-      creator.payer,
-      creator.publicKey,
-      null,
-      6
-    );
+    mint = await createMint(provider.connection, payer, creator, null, 6);
 
     creatorTokenAccount = await createAccount(
       provider.connection,
-      creator.payer,
+      payer,
       mint,
-      creator.publicKey
+      creator
     );
-
-    participantTokenAccount = await createAccount(
-      provider.connection,
-      creator.payer,
-      mint,
-      participant.publicKey
-    );
-
     await mintTo(
       provider.connection,
-      creator.payer,
+      payer,
       mint,
       creatorTokenAccount,
-      creator.publicKey,
+      creator,
       1_000_000_000
     );
 
-    await mintTo(
-      provider.connection,
-      creator.payer,
-      mint,
-      participantTokenAccount,
-      creator.publicKey,
-      1_000_000_000
-    );
-
-    // PDAs
-    [globalStatePda] = anchor.web3.PublicKey.findProgramAddressSync(
-      [Buffer.from("global-state")],
-      program.programId
-    );
-
-    [groupPda] = anchor.web3.PublicKey.findProgramAddressSync(
-      [Buffer.from("ajo-group"), Buffer.from(groupName)],
-      program.programId
-    );
-
-    [groupVaultPda] = anchor.web3.PublicKey.findProgramAddressSync(
-      [Buffer.from("group-vault"), groupPda.toBuffer()],
-      program.programId
-    );
-
-    // Airdrop participant
-    await provider.connection.requestAirdrop(participant.publicKey, 2e9);
+    member = await createMember(provider, mint, 1_000_000_000);
+    thirdMember = await createMember(provider, mint, 1_000_000_000);
   });
 
   it("initializes the global state", async () => {
-    await program.methods
-      .initialize()
-      .accountsStrict({
-        globalState: globalStatePda,
-        admin: creator.publicKey,
-        systemProgram: anchor.web3.SystemProgram.programId,
-      })
-      .rpc();
-
-    const state = await program.account.globalState.fetch(globalStatePda);
-    expect(state.totalGroups.toNumber()).to.equal(0);
-  });
-
-  it("creates an ajo group", async () => {
-    await program.methods
-      .createAjoGroup(
-        groupName,
-        new anchor.BN(1000000), // security_deposit
-        new anchor.BN(100000), // contribution_amount
-        1, // contribution_interval
-        7, // payout_interval
-        3 // num_participants
-      )
-      .accountsStrict({
-        globalState: globalStatePda,
-        ajoGroup: groupPda,
-        creator: creator.publicKey,
-        creatorTokenAccount,
-        groupTokenVault: groupVaultPda,
-        tokenProgram: TOKEN_PROGRAM_ID,
-        systemProgram: anchor.web3.SystemProgram.programId,
-      })
-      .rpc();
+    globalState = await setUpProtocol(program, provider, mint);
 
-    const group = await program.account.ajoGroup.fetch(groupPda);
-    expect(group.name).to.equal(groupName);
-    expect(group.numParticipants).to.equal(3);
-    expect(group.participants.length).to.equal(1);
+    const state = await program.account.globalState.fetch(globalState);
+    expect(state.authority.equals(creator)).to.be.true;
+    expect(state.allowedMints.some((allowed) => allowed.equals(mint))).to.be
+      .true;
   });
 
-  it("joins ajo group", async () => {
-    await program.methods
-      .joinAjoGroup()
-      .accountsStrict({
-        globalState: globalStatePda,
-        ajoGroup: groupPda,
-        participant: participant.publicKey,
-        participantTokenAccount,
-        groupTokenVault: groupVaultPda,
-        tokenProgram: TOKEN_PROGRAM_ID,
-        systemProgram: anchor.web3.SystemProgram.programId,
-      })
-      .signers([participant])
-      .rpc();
+  it("creates a group and starts it once every seat is filled", async () => {
+    group = await createStartedGroup(
+      program,
+      provider,
+      mint,
+      groupName,
+      contributionAmount,
+      [member, thirdMember]
+    );
 
-    const group = await program.account.ajoGroup.fetch(groupPda);
-    expect(group.participants.length).to.equal(2);
-  });
+    const ajoGroup = await program.account.ajoGroup.fetch(group);
+    expect(ajoGroup.name).to.equal(groupName);
+    expect(ajoGroup.seatsFilled).to.equal(3);
+    expect(ajoGroup.activeMembers).to.equal(3);
+    expect(ajoGroup.startTimestamp).to.not.be.null;
 
-  it("fails to contribute before group starts", async () => {
-    try {
-      await program.methods
-        .contribute()
-        .accountsStrict({
-          ajoGroup: groupPda,
-          contributor: participant.publicKey,
-          contributorTokenAccount: participantTokenAccount,
-          groupTokenVault: groupVaultPda,
-          tokenProgram: TOKEN_PROGRAM_ID,
-        })
-        .signers([participant])
-        .rpc();
-    } catch (err) {
-      expect(err.message).to.contain("GroupNotStarted");
-    }
+    const participant = await program.account.ajoParticipant.fetch(
+      participantPda(program.programId, group, member.wallet.publicKey)
+    );
+    expect(participant.seat).to.equal(1);
+    expect(participant.status).to.deep.equal({ active: {} });
   });
 
-  const thirdParticipant = anchor.web3.Keypair.generate();
-  let thirdTokenAccount: anchor.web3.PublicKey;
-
-  it("third participant joins and group starts", async () => {
-    thirdTokenAccount = await createAccount(
-      provider.connection,
-      creator.payer,
+  it("members pay the first round, one of them a round ahead", async () => {
+    // No round has passed yet, so paying a round means paying ahead by one
+    await contribute(program, mint, group, payer, creatorTokenAccount, 1);
+    await contribute(
+      program,
       mint,
-      thirdParticipant.publicKey
+      group,
+      member.wallet,
+      member.tokenAccount,
+      2
     );
-
-    await mintTo(
-      provider.connection,
-      creator.payer,
+    await contribute(
+      program,
       mint,
-      thirdTokenAccount,
-      creator.publicKey,
-      1_000_000_000
+      group,
+      thirdMember.wallet,
+      thirdMember.tokenAccount,
+      1
     );
 
-    await provider.connection.requestAirdrop(thirdParticipant.publicKey, 2e9);
-
-    await program.methods
-      .joinAjoGroup()
-      .accountsStrict({
-        globalState: globalStatePda,
-        ajoGroup: groupPda,
-        participant: thirdParticipant.publicKey,
-        participantTokenAccount: thirdTokenAccount,
-        groupTokenVault: groupVaultPda,
-        tokenProgram: TOKEN_PROGRAM_ID,
-        systemProgram: anchor.web3.SystemProgram.programId,
-      })
-      .signers([thirdParticipant])
-      .rpc();
-
-    const group = await program.account.ajoGroup.fetch(groupPda);
-    expect(group.isActive).to.be.true;
-    expect(group.participants.length).to.equal(3);
-    expect(group.payoutIndex).to.equal(0);
-  });
-
-  it("participants contribute", async () => {
-    const contrib = async (
-      signer: anchor.web3.Keypair,
-      tokenAccount: anchor.web3.PublicKey
-    ) => {
-      await program.methods
-        .contribute()
-        .accountsStrict({
-          ajoGroup: groupPda,
-          contributor: signer.publicKey,
-          contributorTokenAccount: tokenAccount,
-          groupTokenVault: groupVaultPda,
-          tokenProgram: TOKEN_PROGRAM_ID,
-        })
-        .signers([signer])
-        .rpc();
-    };
-
-    // Creator
-    await contrib(creator.payer, creatorTokenAccount);
-    // Participant 1
-    await contrib(participant, participantTokenAccount);
-    // Participant 2
-    await contrib(thirdParticipant, thirdTokenAccount);
+    const vault = await getAccount(
+      provider.connection,
+      vaultPda(program.programId, group)
+    );
+    expect(Number(vault.amount)).to.equal(4 * contributionAmount);
 
-    const group = await program.account.ajoGroup.fetch(groupPda);
-    expect(group.roundContributions.toNumber()).to.equal(3);
+    const participant = await program.account.ajoParticipant.fetch(
+      participantPda(program.programId, group, member.wallet.publicKey)
+    );
+    expect(participant.contributionRound).to.equal(2);
   });
 
-  it("triggers payout to the first recipient", async () => {
-    const recipientIndex = 0;
-    const recipientKey = groupPda; // Just for logic ref — actual recipient is first participant
+  it("pays the first seat once the round has passed", async () => {
+    await waitForNextRound();
 
-    const preVaultBalance = await getAccount(
-      provider.connection,
-      groupVaultPda
-    );
-    const preRecipientBalance = await getAccount(
-      provider.connection,
-      creatorTokenAccount
-    );
+    const before = await getAccount(provider.connection, creatorTokenAccount);
 
     await program.methods
       .payout()
-      .accountsStrict({
-        ajoGroup: groupPda,
-        groupTokenVault: groupVaultPda,
-        recipientTokenAccount: creatorTokenAccount,
+      .accountsPartial({
+        ajoGroup: group,
+        groupTokenVault: vaultPda(program.programId, group),
+        recipient: creatorTokenAccount,
+        recipientParticipant: participantPda(program.programId, group, creator),
+        caller: creator,
+        globalState,
+        treasuryTokenAccount: null,
+        keeperTokenAccount: null,
+        tokenMint: mint,
         tokenProgram: TOKEN_PROGRAM_ID,
       })
       .rpc();
 
-    const postVaultBalance = await getAccount(
-      provider.connection,
-      groupVaultPda
-    );
-    const postRecipientBalance = await getAccount(
-      provider.connection,
-      creatorTokenAccount
+    const after = await getAccount(provider.connection, creatorTokenAccount);
+    expect(Number(after.amount - before.amount)).to.equal(
+      3 * contributionAmount
     );
 
-    expect(postRecipientBalance.amount > preRecipientBalance.amount).to.be.true;
-
-    const group = await program.account.ajoGroup.fetch(groupPda);
-    expect(group.payoutIndex).to.equal(1); // moved to next recipient
+    const ajoGroup = await program.account.ajoGroup.fetch(group);
+    expect(ajoGroup.payoutRound).to.equal(1);
   });
 
-  it("participant votes to close the group", async () => {
-    await program.methods
-      .closeAjoGroup()
-      .accountsStrict({
-        ajoGroup: groupPda,
-        participant: participant.publicKey,
-      })
-      .signers([participant])
-      .rpc();
+  it("closes the group once a majority has voted to", async () => {
+    await voteToClose(program, group, member.wallet);
 
-    const group = await program.account.ajoGroup.fetch(groupPda);
-    expect(group.votedToClose.length).to.equal(1);
-  });
+    let ajoGroup = await program.account.ajoGroup.fetch(group);
+    expect(ajoGroup.closeVoteExpiries.length).to.equal(1);
+    expect(ajoGroup.isClosed).to.be.false;
 
-  it("participant claims refund after closure", async () => {
-    // Manually set group to closed for test shortcut
-    await program.methods
-      .closeAjoGroup()
-      .accountsStrict({
-        ajoGroup: groupPda,
-        participant: creator.publicKey,
-      })
-      .rpc();
+    await voteToClose(program, group, thirdMember.wallet);
 
-    await program.methods
-      .closeAjoGroup()
-      .accountsStrict({
-        ajoGroup: groupPda,
-        participant: thirdParticipant.publicKey,
-      })
-      .signers([thirdParticipant])
-      .rpc();
-
-    const groupAfterClose = await program.account.ajoGroup.fetch(groupPda);
-    expect(groupAfterClose.isClosed).to.be.true;
+    ajoGroup = await program.account.ajoGroup.fetch(group);
+    expect(ajoGroup.isClosed).to.be.true;
+  });
 
-    // Claim refund
-    const preBalance = await getAccount(
-      provider.connection,
-      participantTokenAccount
-    );
+  it("refunds the prepaid round after closing", async () => {
+    const before = await getAccount(provider.connection, member.tokenAccount);
 
     await program.methods
       .claimRefund()
-      .accountsStrict({
-        ajoGroup: groupPda,
-        participant: participant.publicKey,
-        participantTokenAccount: participantTokenAccount,
-        groupTokenVault: groupVaultPda,
+      .accountsPartial({
+        ajoGroup: group,
+        groupTokenVault: vaultPda(program.programId, group),
+        participant: member.wallet.publicKey,
+        ajoParticipant: participantPda(
+          program.programId,
+          group,
+          member.wallet.publicKey
+        ),
+        participantTokenAccount: member.tokenAccount,
+        globalState,
+        tokenMint: mint,
         tokenProgram: TOKEN_PROGRAM_ID,
       })
-      .signers([participant])
+      .signers([member.wallet])
       .rpc();
 
-    const postBalance = await getAccount(
-      provider.connection,
-      participantTokenAccount
+    const after = await getAccount(provider.connection, member.tokenAccount);
+    expect(Number(after.amount - before.amount)).to.equal(contributionAmount);
+
+    const participant = await program.account.ajoParticipant.fetch(
+      participantPda(program.programId, group, member.wallet.publicKey)
+    );
+    expect(participant.refundClaimed).to.be.true;
+  });
+
+  it("has nothing to refund to members who only paid out rounds", async () => {
+    await expectError(
+      program.methods
+        .claimRefund()
+        .accountsPartial({
+          ajoGroup: group,
+          groupTokenVault: vaultPda(program.programId, group),
+          participant: thirdMember.wallet.publicKey,
+          ajoParticipant: participantPda(
+            program.programId,
+            group,
+            thirdMember.wallet.publicKey
+          ),
+          participantTokenAccount: thirdMember.tokenAccount,
+          globalState,
+          tokenMint: mint,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .signers([thirdMember.wallet])
+        .rpc(),
+      "NoRefundToClaim"
     );
-    expect(postBalance.amount > preBalance.amount).to.be.true;
   });
 });
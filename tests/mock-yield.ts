@@ -0,0 +1,353 @@
+import * as anchor from "@coral-xyz/anchor";
+import { Program } from "@coral-xyz/anchor";
+import {
+  TOKEN_PROGRAM_ID,
+  createMint,
+  createAccount,
+  mintTo,
+  getAccount,
+} from "@solana/spl-token";
+import { KoopaContract } from "../target/types/koopa_contract";
+import { MockYield } from "../target/types/mock_yield";
+import { expect } from "chai";
+import {
+  Member,
+  contribute,
+  createMember,
+  createStartedGroup,
+  expectError,
+  participantPda,
+  proposalPda,
+  proposalVotePda,
+  setUpProtocol,
+  vaultPda,
+  voteToClose,
+  waitForNextRound,
+  yieldAuthorityPda,
+} from "./utils";
+
+const { PublicKey, SystemProgram } = anchor.web3;
+
+describe("mock-yield adapter", () => {
+  const provider = anchor.AnchorProvider.env();
+  anchor.setProvider(provider);
+  const program = anchor.workspace.KoopaContract as Program<KoopaContract>;
+  const mockYield = anchor.workspace.MockYield as Program<MockYield>;
+
+  const payer = (provider.wallet as anchor.Wallet).payer;
+  const creator = provider.wallet.publicKey;
+  const groupName = "Yield Group";
+  const contributionAmount = 100_000;
+  const accruedYield = 30_000;
+
+  let mint: anchor.web3.PublicKey;
+  let creatorTokenAccount: anchor.web3.PublicKey;
+  let globalState: anchor.web3.PublicKey;
+  let group: anchor.web3.PublicKey;
+  let vault: anchor.web3.PublicKey;
+  let yieldAuthority: anchor.web3.PublicKey;
+  let position: anchor.web3.PublicKey;
+  let reserve: anchor.web3.PublicKey;
+  let member: Member;
+  let thirdMember: Member;
+  let deposited: number;
+
+  // Adapter accounts in the order koopa-contract expects them in
+  // `remaining_accounts`: the adapter program, the group's yield authority,
+  // the position and the reserve
+  const adapterAccounts = () => [
+    { pubkey: mockYield.programId, isSigner: false, isWritable: false },
+    { pubkey: yieldAuthority, isSigner: false, isWritable: false },
+    { pubkey: position, isSigner: false, isWritable: true },
+    { pubkey: reserve, isSigner: false, isWritable: true },
+  ];
+
+  before(async () => {
+    mint = await createMint(provider.connection, payer, creator, null, 6);
+    creatorTokenAccount = await createAccount(
+      provider.connection,
+      payer,
+      mint,
+      creator
+    );
+    await mintTo(
+      provider.connection,
+      payer,
+      mint,
+      creatorTokenAccount,
+      creator,
+      1_000_000_000
+    );
+    member = await createMember(provider, mint, 1_000_000_000);
+    thirdMember = await createMember(provider, mint, 1_000_000_000);
+
+    globalState = await setUpProtocol(program, provider, mint);
+
+    const state = await program.account.globalState.fetch(globalState);
+    if (!state.yieldAdapters.some((a) => a.equals(mockYield.programId))) {
+      await program.methods
+        .addYieldAdapter(mockYield.programId)
+        .accountsPartial({ globalState, authority: creator })
+        .rpc();
+    }
+
+    [reserve] = PublicKey.findProgramAddressSync(
+      [Buffer.from("reserve"), mint.toBuffer()],
+      mockYield.programId
+    );
+    await mockYield.methods
+      .initializeReserve()
+      .accountsPartial({
+        reserve,
+        mint,
+        payer: creator,
+        tokenProgram: TOKEN_PROGRAM_ID,
+        systemProgram: SystemProgram.programId,
+      })
+      .rpc();
+  });
+
+  it("opts a group into the adapter by vote", async () => {
+    group = await createStartedGroup(
+      program,
+      provider,
+      mint,
+      groupName,
+      contributionAmount,
+      [member, thirdMember]
+    );
+    vault = vaultPda(program.programId, group);
+
+    const proposal = proposalPda(program.programId, group, 0);
+    await program.methods
+      .createProposal(
+        { setYieldAdapter: { adapter: mockYield.programId } },
+        "Park idle funds between payouts",
+        new anchor.BN(60 * 60), // voting_period
+        50, // quorum
+        50 // threshold
+      )
+      .accountsPartial({
+        ajoGroup: group,
+        proposer: creator,
+        proposerParticipant: participantPda(program.programId, group, creator),
+        globalState,
+        proposal,
+        systemProgram: SystemProgram.programId,
+      })
+      .rpc();
+
+    // Two of three yes votes clear the threshold before the deadline
+    for (const voter of [payer, member.wallet]) {
+      await program.methods
+        .voteOnProposal({ yes: {} })
+        .accountsPartial({
+          ajoGroup: group,
+          voter: voter.publicKey,
+          voterParticipant: participantPda(
+            program.programId,
+            group,
+            voter.publicKey
+          ),
+          proposal,
+          vote: proposalVotePda(program.programId, proposal, voter.publicKey),
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([voter])
+        .rpc();
+    }
+
+    await program.methods
+      .executeProposal()
+      .accountsPartial({
+        ajoGroup: group,
+        proposal,
+        globalState,
+        memberParticipant: null,
+        displacedParticipant: null,
+        caller: creator,
+      })
+      .rpc();
+
+    const ajoGroup = await program.account.ajoGroup.fetch(group);
+    expect(ajoGroup.yieldAdapter.equals(mockYield.programId)).to.be.true;
+
+    // The adapter position belongs to the group's yield authority, never to
+    // the group PDA itself
+    yieldAuthority = yieldAuthorityPda(program.programId, group);
+    [position] = PublicKey.findProgramAddressSync(
+      [Buffer.from("position"), yieldAuthority.toBuffer()],
+      mockYield.programId
+    );
+    await mockYield.methods
+      .openPosition(yieldAuthority)
+      .accountsPartial({
+        position,
+        mint,
+        payer: creator,
+        systemProgram: SystemProgram.programId,
+      })
+      .rpc();
+  });
+
+  it("parks the contributions with the adapter", async () => {
+    // Everyone pays the first two rounds up front
+    await contribute(program, mint, group, payer, creatorTokenAccount, 2);
+    await contribute(
+      program,
+      mint,
+      group,
+      member.wallet,
+      member.tokenAccount,
+      2
+    );
+    await contribute(
+      program,
+      mint,
+      group,
+      thirdMember.wallet,
+      thirdMember.tokenAccount,
+      2
+    );
+
+    // Members paid two or three rounds, depending on when the round turned
+    deposited = Number((await getAccount(provider.connection, vault)).amount);
+    await program.methods
+      .depositIdleFunds(new anchor.BN(deposited))
+      .accountsPartial({
+        ajoGroup: group,
+        groupTokenVault: vault,
+        admin: creator,
+        globalState,
+        tokenMint: mint,
+        tokenProgram: TOKEN_PROGRAM_ID,
+      })
+      .remainingAccounts(adapterAccounts())
+      .rpc();
+
+    const vaultAccount = await getAccount(provider.connection, vault);
+    expect(Number(vaultAccount.amount)).to.equal(0);
+    // The delegation only lasts for the deposit
+    expect(vaultAccount.delegate).to.be.null;
+    expect(Number(vaultAccount.delegatedAmount)).to.equal(0);
+
+    const ajoGroup = await program.account.ajoGroup.fetch(group);
+    expect(ajoGroup.yieldDeposited.toNumber()).to.equal(deposited);
+
+    const positionAccount = await mockYield.account.position.fetch(position);
+    expect(positionAccount.value.toNumber()).to.equal(deposited);
+  });
+
+  it("only lets the admin park funds", async () => {
+    await expectError(
+      program.methods
+        .depositIdleFunds(new anchor.BN(1))
+        .accountsPartial({
+          ajoGroup: group,
+          groupTokenVault: vault,
+          admin: member.wallet.publicKey,
+          globalState,
+          tokenMint: mint,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .remainingAccounts(adapterAccounts())
+        .signers([member.wallet])
+        .rpc(),
+      "OnlyAdminCanUpdate"
+    );
+  });
+
+  it("pulls the payout back from the adapter just in time", async () => {
+    await mockYield.methods
+      .accrueYield(new anchor.BN(accruedYield))
+      .accountsPartial({
+        position,
+        reserve,
+        funder: creator,
+        funderTokenAccount: creatorTokenAccount,
+        mint,
+        tokenProgram: TOKEN_PROGRAM_ID,
+      })
+      .rpc();
+
+    await waitForNextRound();
+
+    const before = await getAccount(provider.connection, creatorTokenAccount);
+
+    // No payout shares, so the adapter accounts come first
+    await program.methods
+      .payout()
+      .accountsPartial({
+        ajoGroup: group,
+        groupTokenVault: vault,
+        recipient: creatorTokenAccount,
+        recipientParticipant: participantPda(program.programId, group, creator),
+        caller: creator,
+        globalState,
+        treasuryTokenAccount: null,
+        keeperTokenAccount: null,
+        tokenMint: mint,
+        tokenProgram: TOKEN_PROGRAM_ID,
+      })
+      .remainingAccounts(adapterAccounts())
+      .rpc();
+
+    const after = await getAccount(provider.connection, creatorTokenAccount);
+    expect(Number(after.amount - before.amount)).to.equal(
+      3 * contributionAmount
+    );
+
+    const ajoGroup = await program.account.ajoGroup.fetch(group);
+    expect(ajoGroup.payoutRound).to.equal(1);
+    expect(ajoGroup.yieldDeposited.toNumber()).to.equal(
+      deposited - 3 * contributionAmount
+    );
+  });
+
+  it("withdraws the whole position on the first refund", async () => {
+    await voteToClose(program, group, member.wallet);
+    await voteToClose(program, group, thirdMember.wallet);
+
+    const memberParticipant = participantPda(
+      program.programId,
+      group,
+      member.wallet.publicKey
+    );
+    const { contributionRound } = await program.account.ajoParticipant.fetch(
+      memberParticipant
+    );
+    // Everything paid beyond the one round paid out
+    const refund = (contributionRound - 1) * contributionAmount;
+    const before = await getAccount(provider.connection, member.tokenAccount);
+
+    await program.methods
+      .claimRefund()
+      .accountsPartial({
+        ajoGroup: group,
+        groupTokenVault: vault,
+        participant: member.wallet.publicKey,
+        ajoParticipant: memberParticipant,
+        participantTokenAccount: member.tokenAccount,
+        globalState,
+        tokenMint: mint,
+        tokenProgram: TOKEN_PROGRAM_ID,
+      })
+      .remainingAccounts(adapterAccounts())
+      .signers([member.wallet])
+      .rpc();
+
+    const ajoGroup = await program.account.ajoGroup.fetch(group);
+    expect(ajoGroup.isClosed).to.be.true;
+    expect(ajoGroup.yieldDeposited.toNumber()).to.equal(0);
+    expect(ajoGroup.yieldEarned.toNumber()).to.equal(accruedYield);
+
+    const positionAccount = await mockYield.account.position.fetch(position);
+    expect(positionAccount.value.toNumber()).to.equal(0);
+
+    // The prepaid rounds plus a share of the yield
+    const after = await getAccount(provider.connection, member.tokenAccount);
+    const refunded = Number(after.amount - before.amount);
+    expect(refunded).to.be.greaterThan(refund);
+    expect(refunded).to.be.at.most(refund + accruedYield);
+  });
+});
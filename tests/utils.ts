@@ -0,0 +1,308 @@
+import * as anchor from "@coral-xyz/anchor";
+import { Program } from "@coral-xyz/anchor";
+import { TOKEN_PROGRAM_ID, createAccount, mintTo } from "@solana/spl-token";
+import { KoopaContract } from "../target/types/koopa_contract";
+
+const { PublicKey, Keypair, SystemProgram, LAMPORTS_PER_SOL } = anchor.web3;
+
+// Rounds short enough to pass within a test run, yet long enough that the
+// setup of a test finishes inside one round. Sub-day time units are only
+// allowed once the protocol sets `minIntervalSecs`.
+export const INTERVAL_SECS = 10;
+
+export const sleep = (ms: number) =>
+  new Promise((resolve) => setTimeout(resolve, ms));
+
+export const waitForNextRound = () => sleep((INTERVAL_SECS + 1) * 1000);
+
+export const globalStatePda = (programId: anchor.web3.PublicKey) =>
+  PublicKey.findProgramAddressSync([Buffer.from("global-state")], programId)[0];
+
+export const groupPda = (programId: anchor.web3.PublicKey, name: string) =>
+  PublicKey.findProgramAddressSync(
+    [Buffer.from("ajo-group"), Buffer.from(name)],
+    programId
+  )[0];
+
+export const vaultPda = (
+  programId: anchor.web3.PublicKey,
+  group: anchor.web3.PublicKey
+) =>
+  PublicKey.findProgramAddressSync(
+    [Buffer.from("group-vault"), group.toBuffer()],
+    programId
+  )[0];
+
+export const participantPda = (
+  programId: anchor.web3.PublicKey,
+  group: anchor.web3.PublicKey,
+  wallet: anchor.web3.PublicKey
+) =>
+  PublicKey.findProgramAddressSync(
+    [Buffer.from("participant"), group.toBuffer(), wallet.toBuffer()],
+    programId
+  )[0];
+
+export const proposalPda = (
+  programId: anchor.web3.PublicKey,
+  group: anchor.web3.PublicKey,
+  id: number
+) => {
+  const seed = Buffer.alloc(4);
+  seed.writeUInt32LE(id);
+  return PublicKey.findProgramAddressSync(
+    [Buffer.from("proposal"), group.toBuffer(), seed],
+    programId
+  )[0];
+};
+
+export const proposalVotePda = (
+  programId: anchor.web3.PublicKey,
+  proposal: anchor.web3.PublicKey,
+  voter: anchor.web3.PublicKey
+) =>
+  PublicKey.findProgramAddressSync(
+    [Buffer.from("proposal-vote"), proposal.toBuffer(), voter.toBuffer()],
+    programId
+  )[0];
+
+export const yieldAuthorityPda = (
+  programId: anchor.web3.PublicKey,
+  group: anchor.web3.PublicKey
+) =>
+  PublicKey.findProgramAddressSync(
+    [Buffer.from("yield-authority"), group.toBuffer()],
+    programId
+  )[0];
+
+export const protocolConfig = (treasury: anchor.web3.PublicKey) => ({
+  feePercentage: 0,
+  feeMode: { onContribution: {} },
+  treasury,
+  maxAdminCommission: 50,
+  keeperTip: 0,
+  closeVoteExpirySecs: 0,
+  limits: {
+    minParticipants: 3,
+    maxParticipants: 20,
+    minContributionInterval: 1,
+    maxContributionInterval: 90,
+    minPayoutInterval: 1,
+    maxPayoutInterval: 90,
+    maxNameLength: 50,
+    minIntervalSecs: 1,
+  },
+});
+
+// Initialize the protocol once per validator and allow `mint`; every test
+// file shares the same global state
+export const setUpProtocol = async (
+  program: Program<KoopaContract>,
+  provider: anchor.AnchorProvider,
+  mint: anchor.web3.PublicKey
+) => {
+  const globalState = globalStatePda(program.programId);
+  let state = await program.account.globalState.fetchNullable(globalState);
+
+  if (!state) {
+    const [programData] = PublicKey.findProgramAddressSync(
+      [program.programId.toBuffer()],
+      anchor.web3.BPF_LOADER_UPGRADEABLE_PROGRAM_ID
+    );
+
+    await program.methods
+      .initialize(protocolConfig(provider.wallet.publicKey))
+      .accountsPartial({
+        globalState,
+        admin: provider.wallet.publicKey,
+        program: program.programId,
+        programData,
+        systemProgram: SystemProgram.programId,
+      })
+      .rpc();
+    state = await program.account.globalState.fetch(globalState);
+  }
+
+  if (!state.allowedMints.some((allowed) => allowed.equals(mint))) {
+    await program.methods
+      .addAllowedMint(mint)
+      .accountsPartial({
+        globalState,
+        authority: provider.wallet.publicKey,
+      })
+      .rpc();
+  }
+
+  return globalState;
+};
+
+// A funded wallet holding a token account with `amount` of `mint`
+export const createMember = async (
+  provider: anchor.AnchorProvider,
+  mint: anchor.web3.PublicKey,
+  amount: number
+) => {
+  const wallet = Keypair.generate();
+  const signature = await provider.connection.requestAirdrop(
+    wallet.publicKey,
+    2 * LAMPORTS_PER_SOL
+  );
+  await provider.connection.confirmTransaction(signature, "confirmed");
+
+  const payer = (provider.wallet as anchor.Wallet).payer;
+  const tokenAccount = await createAccount(
+    provider.connection,
+    payer,
+    mint,
+    wallet.publicKey
+  );
+  await mintTo(
+    provider.connection,
+    payer,
+    mint,
+    tokenAccount,
+    provider.wallet.publicKey,
+    amount
+  );
+
+  return { wallet, tokenAccount };
+};
+
+export type Member = Awaited<ReturnType<typeof createMember>>;
+
+// Create a group run in seconds and seat `members` after its creator, which
+// starts it once every seat is filled
+export const createStartedGroup = async (
+  program: Program<KoopaContract>,
+  provider: anchor.AnchorProvider,
+  mint: anchor.web3.PublicKey,
+  name: string,
+  contributionAmount: number,
+  members: Member[]
+) => {
+  const globalState = globalStatePda(program.programId);
+  const group = groupPda(program.programId, name);
+  const creator = provider.wallet.publicKey;
+
+  await program.methods
+    .createAjoGroup(
+      name,
+      new anchor.BN(contributionAmount),
+      INTERVAL_SECS, // contribution_interval
+      INTERVAL_SECS, // payout_interval
+      members.length + 1, // num_participants
+      0, // admin_commission
+      { seconds: {} }
+    )
+    .accountsPartial({
+      ajoGroup: group,
+      creator,
+      creatorParticipant: participantPda(program.programId, group, creator),
+      globalState,
+      tokenMint: mint,
+      groupTokenVault: vaultPda(program.programId, group),
+      tokenProgram: TOKEN_PROGRAM_ID,
+      systemProgram: SystemProgram.programId,
+      rent: anchor.web3.SYSVAR_RENT_PUBKEY,
+    })
+    .rpc();
+
+  for (const member of members) {
+    const ajoParticipant = participantPda(
+      program.programId,
+      group,
+      member.wallet.publicKey
+    );
+
+    await program.methods
+      .requestJoinAjoGroup()
+      .accountsPartial({
+        ajoGroup: group,
+        participant: member.wallet.publicKey,
+        ajoParticipant,
+        tokenProgram: TOKEN_PROGRAM_ID,
+        systemProgram: SystemProgram.programId,
+      })
+      .signers([member.wallet])
+      .rpc();
+
+    await program.methods
+      .approveJoinRequest(true)
+      .accountsPartial({
+        ajoGroup: group,
+        participant: member.wallet.publicKey,
+        ajoParticipant,
+        caller: creator,
+        globalState,
+        systemProgram: SystemProgram.programId,
+      })
+      .rpc();
+  }
+
+  return group;
+};
+
+export const contribute = (
+  program: Program<KoopaContract>,
+  mint: anchor.web3.PublicKey,
+  group: anchor.web3.PublicKey,
+  wallet: anchor.web3.Keypair,
+  tokenAccount: anchor.web3.PublicKey,
+  prepayRounds: number
+) =>
+  program.methods
+    .contribute(prepayRounds)
+    .accountsPartial({
+      ajoGroup: group,
+      contributor: wallet.publicKey,
+      ajoParticipant: participantPda(
+        program.programId,
+        group,
+        wallet.publicKey
+      ),
+      contributorTokenAccount: tokenAccount,
+      groupTokenVault: vaultPda(program.programId, group),
+      tokenMint: mint,
+      globalState: globalStatePda(program.programId),
+      treasuryTokenAccount: null,
+      tokenProgram: TOKEN_PROGRAM_ID,
+      systemProgram: SystemProgram.programId,
+    })
+    .signers([wallet])
+    .rpc();
+
+export const voteToClose = (
+  program: Program<KoopaContract>,
+  group: anchor.web3.PublicKey,
+  wallet: anchor.web3.Keypair
+) =>
+  program.methods
+    .closeAjoGroup()
+    .accountsPartial({
+      ajoGroup: group,
+      participant: wallet.publicKey,
+      ajoParticipant: participantPda(
+        program.programId,
+        group,
+        wallet.publicKey
+      ),
+      globalState: globalStatePda(program.programId),
+      systemProgram: SystemProgram.programId,
+    })
+    .signers([wallet])
+    .rpc();
+
+// Run `action` and check it fails with the program error `code`
+export const expectError = async (action: Promise<unknown>, code: string) => {
+  try {
+    await action;
+  } catch (err) {
+    const errorCode =
+      err instanceof anchor.AnchorError ? err.error.errorCode.code : undefined;
+    if (errorCode !== code) {
+      throw new Error(`expected ${code}, got ${errorCode ?? err}`);
+    }
+    return;
+  }
+  throw new Error(`expected ${code}, but the transaction succeeded`);
+};